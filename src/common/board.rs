@@ -0,0 +1,174 @@
+use super::{frame::FrameData, shape::NodeShape, style::NodeStyle};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path, path::PathBuf, sync::Arc};
+
+/// Plain data description of a single box, independent of any entity.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeData {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageData>,
+    #[serde(default)]
+    pub shape: NodeShape,
+    #[serde(default)]
+    pub style: NodeStyle,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame: Option<FrameData>,
+    /// Locked nodes can't be dragged, resized, nudged or pushed aside.
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageKind {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Bmp,
+    Svg,
+}
+
+impl ImageKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "gif" => Some(Self::Gif),
+            "webp" => Some(Self::Webp),
+            "bmp" => Some(Self::Bmp),
+            "svg" => Some(Self::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// Where an image node's pixels come from: a file on disk, or bytes stored
+/// in the board file itself. Embedded bytes are shared, so the undo history
+/// and clipboard don't copy them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum ImageData {
+    Path {
+        path: PathBuf,
+    },
+    Embedded {
+        kind: ImageKind,
+        #[serde(with = "base64_bytes")]
+        bytes: Arc<[u8]>,
+    },
+}
+
+mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::sync::Arc;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<[u8]>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD
+            .decode(encoded)
+            .map(Arc::from)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A set of boxes, used for undo history, the box clipboard payload and
+/// board files.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub nodes: Vec<NodeData>,
+}
+
+impl BoardSnapshot {
+    /// Text written to the clipboard alongside the payload, so boxes can be
+    /// pasted into applications that don't understand it.
+    pub fn plain_text(&self) -> String {
+        self.nodes
+            .iter()
+            .map(|node| node.text.as_str())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Top-left corner of the area covered by all nodes.
+    pub fn origin(&self) -> Option<(f32, f32)> {
+        self.nodes.iter().fold(None, |origin, node| match origin {
+            None => Some((node.x, node.y)),
+            Some((x, y)) => Some((x.min(node.x), y.min(node.y))),
+        })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        for node in &mut self.nodes {
+            node.x += dx;
+            node.y += dy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(text: &str, x: f32, y: f32) -> NodeData {
+        NodeData {
+            text: text.into(),
+            x,
+            y,
+            width: 10.0,
+            height: 10.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn origin_is_the_top_left_of_all_nodes() {
+        let snapshot = BoardSnapshot {
+            nodes: vec![node("a", 30.0, 5.0), node("b", 10.0, 20.0)],
+        };
+        assert_eq!(snapshot.origin(), Some((10.0, 5.0)));
+        assert_eq!(BoardSnapshot::default().origin(), None);
+    }
+
+    #[test]
+    fn translate_moves_every_node() {
+        let mut snapshot = BoardSnapshot {
+            nodes: vec![node("a", 30.0, 5.0), node("b", 10.0, 20.0)],
+        };
+        snapshot.translate(-10.0, 5.0);
+        assert_eq!(snapshot.origin(), Some((0.0, 10.0)));
+        assert_eq!((snapshot.nodes[0].x, snapshot.nodes[0].y), (20.0, 10.0));
+    }
+
+    #[test]
+    fn plain_text_skips_empty_nodes() {
+        let snapshot = BoardSnapshot {
+            nodes: vec![node("a", 0.0, 0.0), node("", 0.0, 0.0), node("b", 0.0, 0.0)],
+        };
+        assert_eq!(snapshot.plain_text(), "a\n\nb");
+    }
+}
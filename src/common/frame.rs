@@ -0,0 +1,115 @@
+use gpui::*;
+use serde::{Deserialize, Serialize};
+
+/// Height in world units of a frame's title bar, which is all that shows of
+/// a collapsed frame.
+pub const FRAME_TITLE_HEIGHT: f32 = 32.0;
+/// Space in world units left around boxes wrapped in a new frame.
+pub const FRAME_PADDING: f32 = 20.0;
+
+/// Marks a node as a frame: a region that carries the nodes lying inside it.
+/// Membership is purely geometric, so dragging a box in or out of a frame's
+/// bounds is all it takes to add or remove it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrameData {
+    pub collapsed: bool,
+}
+
+/// Innermost frame wholly enclosing each box, given every box's bounds and
+/// whether it is a frame. A frame only encloses boxes smaller than itself,
+/// so two frames can never contain each other.
+pub fn parents(boxes: &[(Bounds<Pixels>, bool)]) -> Vec<Option<usize>> {
+    boxes
+        .iter()
+        .enumerate()
+        .map(|(idx, (bounds, _))| {
+            boxes
+                .iter()
+                .enumerate()
+                .filter(|(frame_idx, (frame, is_frame))| {
+                    *is_frame
+                        && *frame_idx != idx
+                        && encloses(frame, bounds)
+                        && area(bounds) < area(frame)
+                })
+                .min_by(|(_, (a, _)), (_, (b, _))| area(a).total_cmp(&area(b)))
+                .map(|(frame_idx, _)| frame_idx)
+        })
+        .collect()
+}
+
+/// Boxes inside `idx`, including those inside frames nested in it.
+pub fn descendants(parents: &[Option<usize>], idx: usize) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut pending = vec![idx];
+    while let Some(frame) = pending.pop() {
+        for (child, parent) in parents.iter().enumerate() {
+            if *parent == Some(frame) {
+                found.push(child);
+                pending.push(child);
+            }
+        }
+    }
+    found
+}
+
+/// Whether any frame enclosing `idx`, directly or not, satisfies `predicate`.
+pub fn any_ancestor(
+    parents: &[Option<usize>],
+    idx: usize,
+    mut predicate: impl FnMut(usize) -> bool,
+) -> bool {
+    let mut current = parents.get(idx).copied().flatten();
+    while let Some(frame) = current {
+        if predicate(frame) {
+            return true;
+        }
+        current = parents.get(frame).copied().flatten();
+    }
+    false
+}
+
+fn encloses(outer: &Bounds<Pixels>, inner: &Bounds<Pixels>) -> bool {
+    outer.left() <= inner.left()
+        && outer.top() <= inner.top()
+        && inner.right() <= outer.right()
+        && inner.bottom() <= outer.bottom()
+}
+
+fn area(bounds: &Bounds<Pixels>) -> f32 {
+    f32::from(bounds.size.width) * f32::from(bounds.size.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extent(x: f32, y: f32, width: f32, height: f32) -> Bounds<Pixels> {
+        Bounds::new(point(px(x), px(y)), size(px(width), px(height)))
+    }
+
+    #[test]
+    fn boxes_belong_to_their_innermost_frame() {
+        let boxes = [
+            (extent(0.0, 0.0, 200.0, 200.0), true),
+            (extent(10.0, 10.0, 100.0, 100.0), true),
+            (extent(20.0, 20.0, 10.0, 10.0), false),
+            (extent(150.0, 150.0, 10.0, 10.0), false),
+            (extent(300.0, 300.0, 10.0, 10.0), false),
+        ];
+        assert_eq!(parents(&boxes), [None, Some(0), Some(1), Some(0), None]);
+    }
+
+    #[test]
+    fn only_larger_frames_enclose() {
+        let boxes = [
+            (extent(0.0, 0.0, 100.0, 100.0), true),
+            (extent(0.0, 0.0, 100.0, 100.0), true),
+            (extent(10.0, 10.0, 10.0, 10.0), false),
+            (extent(5.0, 5.0, 50.0, 50.0), false),
+        ];
+        // Plain boxes never contain anything, even boxes lying inside them
+        assert_eq!(parents(&boxes), [None, None, Some(0), Some(0)]);
+    }
+}
@@ -0,0 +1,78 @@
+/// Undo/redo stacks of board states.
+///
+/// Callers push the state from *before* a change; undoing swaps the current
+/// state for the most recent pushed one.
+#[derive(Clone)]
+pub struct History<T> {
+    undo_stack: Vec<T>,
+    redo_stack: Vec<T>,
+    limit: usize,
+}
+
+impl<T> History<T> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            limit,
+        }
+    }
+
+    pub fn push(&mut self, state: T) {
+        self.undo_stack.push(state);
+        self.redo_stack.clear();
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo_stack.pop()?;
+        self.redo_stack.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo_stack.pop()?;
+        self.undo_stack.push(current);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_swap_states() {
+        let mut history = History::new(10);
+        history.push(1);
+        history.push(2);
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), None);
+    }
+
+    #[test]
+    fn pushing_clears_redo() {
+        let mut history = History::new(10);
+        history.push(1);
+        assert_eq!(history.undo(2), Some(1));
+        history.push(1);
+        assert_eq!(history.redo(3), None);
+    }
+
+    #[test]
+    fn oldest_states_fall_off_past_the_limit() {
+        let mut history = History::new(2);
+        for state in 1..=3 {
+            history.push(state);
+        }
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), None);
+    }
+}
@@ -0,0 +1,66 @@
+use super::board::{ImageData, ImageKind, NodeData};
+use std::{fs, io::Cursor, path::Path};
+
+/// Longest side in world units of a newly inserted image node.
+const MAX_IMAGE_SIDE: f32 = 300.0;
+
+/// Intrinsic size of an encoded image, in pixels.
+fn image_size(kind: ImageKind, bytes: &[u8]) -> Option<(f32, f32)> {
+    match kind {
+        ImageKind::Svg => {
+            let tree = usvg::Tree::from_data(bytes, &usvg::Options::default()).ok()?;
+            Some((tree.size().width(), tree.size().height()))
+        }
+        _ => {
+            let (width, height) = image::ImageReader::new(Cursor::new(bytes))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()?;
+            Some((width as f32, height as f32))
+        }
+    }
+}
+
+/// Builds an image node with its top-left corner at `(x, y)`, scaled down to
+/// fit `MAX_IMAGE_SIDE` while keeping the image's aspect ratio.
+fn image_node(image: ImageData, size: Option<(f32, f32)>, x: f32, y: f32) -> NodeData {
+    let (width, height) = size.unwrap_or((MAX_IMAGE_SIDE, MAX_IMAGE_SIDE));
+    let scale = (MAX_IMAGE_SIDE / width.max(height)).min(1.0);
+    NodeData {
+        text: String::new(),
+        x,
+        y,
+        width: width * scale,
+        height: height * scale,
+        image: Some(image),
+        ..Default::default()
+    }
+}
+
+pub fn image_node_from_path(path: &Path, x: f32, y: f32) -> Option<NodeData> {
+    let kind = ImageKind::from_path(path)?;
+    let bytes = fs::read(path).ok()?;
+    let image = ImageData::Path {
+        path: path.to_path_buf(),
+    };
+    Some(image_node(image, image_size(kind, &bytes), x, y))
+}
+
+pub fn image_node_from_bytes(kind: ImageKind, bytes: Vec<u8>, x: f32, y: f32) -> NodeData {
+    let size = image_size(kind, &bytes);
+    let bytes = bytes.into();
+    image_node(ImageData::Embedded { kind, bytes }, size, x, y)
+}
+
+/// Replaces a file reference with the file's contents, so the board no
+/// longer depends on the file.
+pub fn embed(image: &ImageData) -> Option<ImageData> {
+    match image {
+        ImageData::Path { path } => Some(ImageData::Embedded {
+            kind: ImageKind::from_path(path)?,
+            bytes: fs::read(path).ok()?.into(),
+        }),
+        ImageData::Embedded { .. } => None,
+    }
+}
@@ -1,8 +1,49 @@
 use gpui::*;
+use serde::de::DeserializeOwned;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub mod board;
+pub mod frame;
+pub mod history;
+pub mod images;
+pub mod search;
+pub mod settings;
+pub mod shape;
+pub mod style;
+pub mod theme;
 
 #[derive(Clone, Debug)]
 pub struct DragState;
 
+/// Directory holding user configuration such as themes.
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("unfold"))
+}
+
+/// First of `names` that exists in the config directory.
+pub fn find_config(names: &[&str]) -> Option<PathBuf> {
+    let dir = config_dir()?;
+    names
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Reads a configuration file as TOML, or as JSON for any other extension.
+pub fn load_config<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|err| err.to_string()),
+        _ => serde_json::from_str(&contents).map_err(|err| err.to_string()),
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Viewport {
     pub zoom: f32,
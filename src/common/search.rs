@@ -0,0 +1,155 @@
+use regex::{Captures, Regex, RegexBuilder};
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    /// Treat the query as a regular expression rather than literal text.
+    pub regex: bool,
+}
+
+/// A compiled search query.
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    regex: Regex,
+    /// Whether `$1` and `${name}` in replacements refer to capture groups.
+    expand_groups: bool,
+}
+
+impl SearchQuery {
+    /// Compiles `query`, returning the regex error message when it's invalid.
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, String> {
+        let pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map(|regex| Self {
+                regex,
+                expand_groups: options.regex,
+            })
+            .map_err(|err| err.to_string())
+    }
+
+    /// Byte ranges of every non-empty match in `text`.
+    pub fn find_in(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// What the match at `range` in `text` is replaced with, or `None` if
+    /// no match sits exactly there any more.
+    pub fn replacement_for(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        replacement: &str,
+    ) -> Option<String> {
+        let captures = self.regex.captures_at(text, range.start)?;
+        if captures.get(0)?.range() != range {
+            return None;
+        }
+        let mut expanded = String::new();
+        self.expand(&captures, replacement, &mut expanded);
+        Some(expanded)
+    }
+
+    /// `text` with every non-empty match replaced.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        let mut replaced = String::with_capacity(text.len());
+        let mut last_end = 0;
+        for captures in self.regex.captures_iter(text) {
+            let Some(found) = captures.get(0).filter(|found| !found.is_empty()) else {
+                continue;
+            };
+            replaced.push_str(&text[last_end..found.start()]);
+            self.expand(&captures, replacement, &mut replaced);
+            last_end = found.end();
+        }
+        replaced.push_str(&text[last_end..]);
+        replaced
+    }
+
+    fn expand(&self, captures: &Captures, replacement: &str, dst: &mut String) {
+        if self.expand_groups {
+            captures.expand(replacement, dst);
+        } else {
+            dst.push_str(replacement);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(query: &str, regex: bool) -> SearchQuery {
+        let options = SearchOptions {
+            case_sensitive: false,
+            regex,
+        };
+        SearchQuery::new(query, options).unwrap()
+    }
+
+    #[test]
+    fn literal_queries_ignore_regex_syntax() {
+        assert_eq!(query("a.b", false).find_in("a.b axb A.B"), [0..3, 8..11]);
+    }
+
+    #[test]
+    fn case_sensitive_queries_match_case() {
+        let options = SearchOptions {
+            case_sensitive: true,
+            regex: false,
+        };
+        let query = SearchQuery::new("Cat", options).unwrap();
+        assert_eq!(query.find_in("cat Cat"), [4..7]);
+    }
+
+    #[test]
+    fn empty_matches_are_skipped() {
+        assert!(query("x*", true).find_in("abc").is_empty());
+    }
+
+    #[test]
+    fn literal_queries_replace_text_as_is() {
+        let query = query("a.b", false);
+        assert_eq!(query.replace_all("a.b axb A.B", "$1"), "$1 axb $1");
+    }
+
+    #[test]
+    fn regex_replacements_expand_groups() {
+        let query = query(r"(\w+)@(\w+)", true);
+        assert_eq!(
+            query.replace_all("me@home you@work", "$2 at $1"),
+            "home at me work at you"
+        );
+    }
+
+    #[test]
+    fn empty_matches_are_left_alone() {
+        assert_eq!(query("x*", true).replace_all("abc", "-"), "abc");
+    }
+
+    #[test]
+    fn replacement_needs_a_match_at_the_range() {
+        let query = query("b+", true);
+        assert_eq!(query.replacement_for("abbbc", 1..4, "x"), Some("x".into()));
+        assert_eq!(query.replacement_for("abbbc", 2..4, "x"), None);
+    }
+
+    #[test]
+    fn invalid_regexes_are_reported() {
+        let options = SearchOptions {
+            case_sensitive: false,
+            regex: true,
+        };
+        assert!(SearchQuery::new("(", options).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use super::{find_config, load_config};
+use gpui::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How much of a box is drawn at the current zoom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelOfDetail {
+    /// Editable text at its zoomed size.
+    Full,
+    /// A short label at a readable size in place of the text.
+    Title,
+    /// A bar standing in for the text, which isn't shaped at all.
+    Placeholder,
+}
+
+/// Zoom levels below which boxes are drawn with less detail.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelOfDetailSettings {
+    pub title_below_zoom: f32,
+    pub placeholder_below_zoom: f32,
+}
+
+impl Default for LevelOfDetailSettings {
+    fn default() -> Self {
+        Self {
+            title_below_zoom: 0.4,
+            placeholder_below_zoom: 0.15,
+        }
+    }
+}
+
+impl LevelOfDetailSettings {
+    pub fn at_zoom(&self, zoom: f32) -> LevelOfDetail {
+        if zoom < self.placeholder_below_zoom {
+            LevelOfDetail::Placeholder
+        } else if zoom < self.title_below_zoom {
+            LevelOfDetail::Title
+        } else {
+            LevelOfDetail::Full
+        }
+    }
+}
+
+/// How the text caret is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaretStyle {
+    /// A thin line between characters.
+    #[default]
+    Bar,
+    /// A box over the character after the caret.
+    Block,
+    /// A line under the character after the caret.
+    Underline,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaretSettings {
+    pub style: CaretStyle,
+    /// Whether the caret blinks. It stays solid while typing either way.
+    pub blink: bool,
+    /// Time between blinks, raised to at least 100 ms.
+    pub blink_interval_ms: u64,
+}
+
+impl Default for CaretSettings {
+    fn default() -> Self {
+        Self {
+            style: CaretStyle::default(),
+            blink: true,
+            blink_interval_ms: 500,
+        }
+    }
+}
+
+impl CaretSettings {
+    const MIN_BLINK_INTERVAL_MS: u64 = 100;
+
+    pub fn blink_interval(&self) -> Duration {
+        Duration::from_millis(self.blink_interval_ms.max(Self::MIN_BLINK_INTERVAL_MS))
+    }
+}
+
+/// User settings, read from `<config dir>/settings.{json,toml}`. Anything
+/// left out keeps its default.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub level_of_detail: LevelOfDetailSettings,
+    pub caret: CaretSettings,
+}
+
+impl Global for Settings {}
+
+impl Settings {
+    pub fn get(cx: &App) -> &Self {
+        cx.global::<Self>()
+    }
+
+    pub fn init(cx: &mut App) {
+        let settings = match find_config(&["settings.json", "settings.toml"]) {
+            Some(path) => load_config(&path).unwrap_or_else(|err| {
+                eprintln!("Failed to load settings {}: {err}", path.display());
+                Self::default()
+            }),
+            None => Self::default(),
+        };
+        cx.set_global(settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detail_drops_as_zoom_falls() {
+        let settings = LevelOfDetailSettings::default();
+        assert_eq!(settings.at_zoom(1.0), LevelOfDetail::Full);
+        assert_eq!(settings.at_zoom(0.4), LevelOfDetail::Full);
+        assert_eq!(settings.at_zoom(0.3), LevelOfDetail::Title);
+        assert_eq!(settings.at_zoom(0.1), LevelOfDetail::Placeholder);
+    }
+
+    #[test]
+    fn blink_interval_has_a_floor() {
+        let caret = CaretSettings {
+            blink_interval_ms: 0,
+            ..Default::default()
+        };
+        assert_eq!(caret.blink_interval(), Duration::from_millis(100));
+        assert_eq!(
+            CaretSettings::default().blink_interval(),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn partial_settings_keep_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{ "level_of_detail": { "title_below_zoom": 0.5 } }"#).unwrap();
+        assert_eq!(settings.level_of_detail.title_below_zoom, 0.5);
+        assert_eq!(settings.level_of_detail.placeholder_below_zoom, 0.15);
+    }
+}
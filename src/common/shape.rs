@@ -0,0 +1,157 @@
+use gpui::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+/// Number of edges used to approximate an ellipse for collision tests.
+const ELLIPSE_SEGMENTS: usize = 32;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeShape {
+    #[default]
+    Rectangle,
+    Rounded,
+    Ellipse,
+    Diamond,
+    StickyNote,
+}
+
+impl NodeShape {
+    /// Fraction of the width and height kept clear of text on each side, so
+    /// text stays inside the visible shape.
+    pub fn text_inset(self) -> (f32, f32) {
+        match self {
+            Self::Rectangle => (0.0, 0.0),
+            Self::Rounded => (0.05, 0.05),
+            // Largest rectangle inscribed in an ellipse spans 1/sqrt(2) of each axis
+            Self::Ellipse => (0.147, 0.147),
+            Self::Diamond => (0.25, 0.25),
+            Self::StickyNote => (0.08, 0.08),
+        }
+    }
+
+    /// Whether text sits in the middle of the shape rather than at the top.
+    pub fn centers_text(self) -> bool {
+        matches!(self, Self::Ellipse | Self::Diamond)
+    }
+
+    pub fn corner_radius(self, bounds: Size<Pixels>) -> Pixels {
+        match self {
+            Self::Rounded => px(f32::from(bounds.width).min(f32::from(bounds.height)) * 0.15),
+            _ => px(0.0),
+        }
+    }
+
+    pub fn contains(self, bounds: Bounds<Pixels>, point: Point<Pixels>) -> bool {
+        if !bounds.contains(&point) {
+            return false;
+        }
+        let center = bounds.center();
+        let dx = f32::from(point.x - center.x) / f32::from(bounds.size.width / 2.0);
+        let dy = f32::from(point.y - center.y) / f32::from(bounds.size.height / 2.0);
+        match self {
+            Self::Ellipse => dx * dx + dy * dy <= 1.0,
+            Self::Diamond => dx.abs() + dy.abs() <= 1.0,
+            Self::Rectangle | Self::Rounded | Self::StickyNote => true,
+        }
+    }
+
+    /// Convex polygon approximating the shape's outline, clockwise from the top.
+    pub fn outline(self, bounds: Bounds<Pixels>) -> Vec<Point<Pixels>> {
+        let center = bounds.center();
+        let rx = bounds.size.width / 2.0;
+        let ry = bounds.size.height / 2.0;
+        match self {
+            Self::Ellipse => (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * TAU;
+                    point(center.x + rx * angle.sin(), center.y - ry * angle.cos())
+                })
+                .collect(),
+            Self::Diamond => vec![
+                point(center.x, bounds.top()),
+                point(bounds.right(), center.y),
+                point(center.x, bounds.bottom()),
+                point(bounds.left(), center.y),
+            ],
+            Self::Rectangle | Self::Rounded | Self::StickyNote => vec![
+                bounds.origin,
+                bounds.top_right(),
+                bounds.bottom_right(),
+                bounds.bottom_left(),
+            ],
+        }
+    }
+
+    /// Separating axis test between two shapes' outlines.
+    pub fn overlaps(
+        self,
+        bounds: Bounds<Pixels>,
+        other: NodeShape,
+        other_bounds: Bounds<Pixels>,
+    ) -> bool {
+        if !bounds.intersects(&other_bounds) {
+            return false;
+        }
+        let a = self.outline(bounds);
+        let b = other.outline(other_bounds);
+        !has_separating_axis(&a, &b) && !has_separating_axis(&b, &a)
+    }
+}
+
+fn has_separating_axis(polygon: &[Point<Pixels>], other: &[Point<Pixels>]) -> bool {
+    (0..polygon.len()).any(|i| {
+        let start = polygon[i];
+        let end = polygon[(i + 1) % polygon.len()];
+        let normal = (f32::from(end.y - start.y), f32::from(start.x - end.x));
+        let (min_a, max_a) = project(polygon, normal);
+        let (min_b, max_b) = project(other, normal);
+        max_a <= min_b || max_b <= min_a
+    })
+}
+
+fn project(polygon: &[Point<Pixels>], axis: (f32, f32)) -> (f32, f32) {
+    polygon
+        .iter()
+        .map(|p| f32::from(p.x) * axis.0 + f32::from(p.y) * axis.1)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32) -> Bounds<Pixels> {
+        Bounds::new(point(px(x), px(y)), size(px(100.0), px(100.0)))
+    }
+
+    #[test]
+    fn corners_lie_outside_round_shapes() {
+        let bounds = square(0.0, 0.0);
+        let corner = point(px(5.0), px(5.0));
+        assert!(NodeShape::Rectangle.contains(bounds, corner));
+        assert!(!NodeShape::Ellipse.contains(bounds, corner));
+        assert!(!NodeShape::Diamond.contains(bounds, corner));
+        for shape in [NodeShape::Rectangle, NodeShape::Ellipse, NodeShape::Diamond] {
+            assert!(shape.contains(bounds, point(px(50.0), px(50.0))));
+            assert!(!shape.contains(bounds, point(px(150.0), px(50.0))));
+        }
+    }
+
+    #[test]
+    fn shapes_touching_only_at_their_bounds_corners_miss() {
+        let (a, b) = (square(0.0, 0.0), square(90.0, 90.0));
+        assert!(NodeShape::Rectangle.overlaps(a, NodeShape::Rectangle, b));
+        assert!(!NodeShape::Ellipse.overlaps(a, NodeShape::Ellipse, b));
+        assert!(!NodeShape::Diamond.overlaps(a, NodeShape::Rectangle, b));
+    }
+
+    #[test]
+    fn shapes_side_by_side_overlap() {
+        let (a, b) = (square(0.0, 0.0), square(90.0, 0.0));
+        assert!(NodeShape::Ellipse.overlaps(a, NodeShape::Diamond, b));
+        assert!(!NodeShape::Rectangle.overlaps(a, NodeShape::Rectangle, square(200.0, 0.0)));
+    }
+}
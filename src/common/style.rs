@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Visual properties of a box. Colors are `0xRRGGBB`; unset values fall back
+/// to the defaults for the box's shape.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeStyle {
+    pub fill: Option<u32>,
+    pub border_color: Option<u32>,
+    /// Border width in world units.
+    pub border_width: f32,
+    /// Corner radius in world units.
+    pub corner_radius: Option<f32>,
+    pub text_color: Option<u32>,
+    pub font_family: Option<String>,
+    /// Font size in world units.
+    pub font_size: f32,
+    pub alignment: TextAlignment,
+}
+
+impl Default for NodeStyle {
+    fn default() -> Self {
+        Self {
+            fill: None,
+            border_color: None,
+            border_width: 0.0,
+            corner_radius: None,
+            text_color: None,
+            font_family: None,
+            font_size: DEFAULT_FONT_SIZE,
+            alignment: TextAlignment::default(),
+        }
+    }
+}
+
+/// A single property change made from the style inspector.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StyleEdit {
+    Fill(Option<u32>),
+    BorderColor(Option<u32>),
+    BorderWidth(f32),
+    CornerRadius(Option<f32>),
+    TextColor(Option<u32>),
+    FontFamily(Option<String>),
+    FontSize(f32),
+    Alignment(TextAlignment),
+}
+
+impl NodeStyle {
+    pub fn apply(&mut self, edit: StyleEdit) {
+        match edit {
+            StyleEdit::Fill(fill) => self.fill = fill,
+            StyleEdit::BorderColor(color) => self.border_color = color,
+            StyleEdit::BorderWidth(width) => self.border_width = width.max(0.0),
+            StyleEdit::CornerRadius(radius) => self.corner_radius = radius.map(|r| r.max(0.0)),
+            StyleEdit::TextColor(color) => self.text_color = color,
+            StyleEdit::FontFamily(family) => self.font_family = family,
+            StyleEdit::FontSize(size) => self.font_size = size.max(1.0),
+            StyleEdit::Alignment(alignment) => self.alignment = alignment,
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use super::{find_config, load_config};
+use gpui::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl From<WindowAppearance> for Appearance {
+    fn from(appearance: WindowAppearance) -> Self {
+        match appearance {
+            WindowAppearance::Light | WindowAppearance::VibrantLight => Self::Light,
+            WindowAppearance::Dark | WindowAppearance::VibrantDark => Self::Dark,
+        }
+    }
+}
+
+/// Colors used by every component. User themes are read from JSON or TOML
+/// with colors written as `"#RRGGBB"` or `"#RRGGBBAA"`; missing colors fall
+/// back to the built-in theme with the same appearance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub appearance: Appearance,
+    #[serde(with = "hex_color")]
+    pub canvas_background: Hsla,
+    #[serde(with = "hex_color")]
+    pub node_fill: Hsla,
+    #[serde(with = "hex_color")]
+    pub node_text: Hsla,
+    #[serde(with = "hex_color")]
+    pub sticky_note_fill: Hsla,
+    #[serde(with = "hex_color")]
+    pub sticky_note_text: Hsla,
+    #[serde(with = "hex_color")]
+    pub sticky_note_fold: Hsla,
+    #[serde(with = "hex_color")]
+    pub frame_background: Hsla,
+    #[serde(with = "hex_color")]
+    pub frame_border: Hsla,
+    #[serde(with = "hex_color")]
+    pub frame_title: Hsla,
+    /// Outline of selected and focused boxes.
+    #[serde(with = "hex_color")]
+    pub accent: Hsla,
+    #[serde(with = "hex_color")]
+    pub text_cursor: Hsla,
+    #[serde(with = "hex_color")]
+    pub text_selection: Hsla,
+    #[serde(with = "hex_color")]
+    pub placeholder_text: Hsla,
+    #[serde(with = "hex_color")]
+    pub search_match: Hsla,
+    /// The search match currently jumped to.
+    #[serde(with = "hex_color")]
+    pub search_match_active: Hsla,
+    #[serde(with = "hex_color")]
+    pub panel_background: Hsla,
+    #[serde(with = "hex_color")]
+    pub panel_border: Hsla,
+    #[serde(with = "hex_color")]
+    pub panel_text: Hsla,
+    #[serde(with = "hex_color")]
+    pub panel_button_active: Hsla,
+    #[serde(with = "hex_color")]
+    pub panel_button_hover: Hsla,
+}
+
+impl Global for Theme {}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "Light".into(),
+            appearance: Appearance::Light,
+            canvas_background: rgb(0xEEEEEE).into(),
+            node_fill: rgb(0x2D3142).into(),
+            node_text: rgb(0xFFFFFF).into(),
+            sticky_note_fill: rgb(0xFFE680).into(),
+            sticky_note_text: rgb(0x3D3D3D).into(),
+            sticky_note_fold: rgb(0xE6C94F).into(),
+            frame_background: rgba(0xFFFFFF99).into(),
+            frame_border: rgb(0xB8BCC8).into(),
+            frame_title: rgb(0x4A4E5C).into(),
+            accent: rgb(0x4F7CFF).into(),
+            text_cursor: rgb(0x0000FF).into(),
+            text_selection: rgba(0x3311ff30).into(),
+            placeholder_text: rgba(0x00000033).into(),
+            search_match: rgba(0xFFD16680).into(),
+            search_match_active: rgba(0xFF9F1CB0).into(),
+            panel_background: rgb(0xFFFFFF).into(),
+            panel_border: rgb(0xCCCCCC).into(),
+            panel_text: rgb(0x333333).into(),
+            panel_button_active: rgb(0xDDE6FF).into(),
+            panel_button_hover: rgb(0xEEEEEE).into(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark".into(),
+            appearance: Appearance::Dark,
+            canvas_background: rgb(0x1E1F24).into(),
+            node_fill: rgb(0x3A3F58).into(),
+            node_text: rgb(0xF0F0F0).into(),
+            sticky_note_fill: rgb(0xE8D26A).into(),
+            sticky_note_text: rgb(0x2B2B2B).into(),
+            sticky_note_fold: rgb(0xC9B24E).into(),
+            frame_background: rgba(0x2A2C3499).into(),
+            frame_border: rgb(0x4A4D5A).into(),
+            frame_title: rgb(0xC8CAD4).into(),
+            accent: rgb(0x6C8CFF).into(),
+            text_cursor: rgb(0x8AB4FF).into(),
+            text_selection: rgba(0x6C8CFF40).into(),
+            placeholder_text: rgba(0xFFFFFF40).into(),
+            search_match: rgba(0xC9A22760).into(),
+            search_match_active: rgba(0xFF9F1C90).into(),
+            panel_background: rgb(0x2A2B31).into(),
+            panel_border: rgb(0x44464F).into(),
+            panel_text: rgb(0xDDDDDD).into(),
+            panel_button_active: rgb(0x3A4670).into(),
+            panel_button_hover: rgb(0x34363D).into(),
+        }
+    }
+
+    pub fn get(cx: &App) -> &Self {
+        cx.global::<Self>()
+    }
+
+    /// Reads a user theme, laid over the built-in theme matching its
+    /// `appearance`, which is light if left out.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let Value::Object(overrides) = load_config(path)? else {
+            return Err("expected a table of theme colors".into());
+        };
+        let appearance = match overrides.get("appearance") {
+            Some(appearance) => {
+                Appearance::deserialize(appearance).map_err(|err| err.to_string())?
+            }
+            None => Appearance::default(),
+        };
+        let base = match appearance {
+            Appearance::Light => Self::light(),
+            Appearance::Dark => Self::dark(),
+        };
+        let Value::Object(mut theme) = serde_json::to_value(base).map_err(|err| err.to_string())?
+        else {
+            unreachable!("themes serialize to a map");
+        };
+        theme.extend(overrides);
+        serde_json::from_value(Value::Object(theme)).map_err(|err| err.to_string())
+    }
+}
+
+/// The light and dark themes to pick from as the system appearance changes.
+pub struct Themes {
+    light: Theme,
+    dark: Theme,
+}
+
+impl Global for Themes {}
+
+impl Themes {
+    /// Loads the built-in themes, replacing one with the user's theme if
+    /// `UNFOLD_THEME` or `<config dir>/theme.{json,toml}` points at one, and
+    /// activates the theme matching the system appearance.
+    pub fn init(cx: &mut App) {
+        let mut themes = Self {
+            light: Theme::light(),
+            dark: Theme::dark(),
+        };
+
+        let user_theme_path = std::env::var_os("UNFOLD_THEME")
+            .map(Into::into)
+            .or_else(|| find_config(&["theme.json", "theme.toml"]));
+        if let Some(path) = user_theme_path {
+            match Theme::load(&path) {
+                Ok(theme) => match theme.appearance {
+                    Appearance::Light => themes.light = theme,
+                    Appearance::Dark => themes.dark = theme,
+                },
+                Err(err) => eprintln!("Failed to load theme {}: {err}", path.display()),
+            }
+        }
+
+        let appearance = cx.window_appearance().into();
+        cx.set_global(themes);
+        Self::activate(appearance, cx);
+    }
+
+    pub fn activate(appearance: Appearance, cx: &mut App) {
+        let themes = cx.global::<Self>();
+        let theme = match appearance {
+            Appearance::Light => themes.light.clone(),
+            Appearance::Dark => themes.dark.clone(),
+        };
+        cx.set_global(theme);
+    }
+}
+
+mod hex_color {
+    use gpui::{rgba, Hsla, Rgba};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Hsla, serializer: S) -> Result<S::Ok, S::Error> {
+        let color = Rgba::from(*color);
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+        serializer.serialize_str(&format!(
+            "#{:02X}{:02X}{:02X}{:02X}",
+            channel(color.r),
+            channel(color.g),
+            channel(color.b),
+            channel(color.a)
+        ))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hsla, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let hex = value.trim_start_matches('#');
+        let parsed = u32::from_str_radix(hex, 16).map_err(serde::de::Error::custom)?;
+        match hex.len() {
+            6 => Ok(rgba((parsed << 8) | 0xFF).into()),
+            8 => Ok(rgba(parsed).into()),
+            _ => Err(serde::de::Error::custom(format!(
+                "expected #RRGGBB or #RRGGBBAA, got {value}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn load(file_name: &str, contents: &str) -> Result<Theme, String> {
+        let path = std::env::temp_dir().join(format!("unfold-{}-{file_name}", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let theme = Theme::load(&path);
+        fs::remove_file(&path).unwrap();
+        theme
+    }
+
+    /// Themes as written to disk, so colors compare at hex precision.
+    fn written(theme: &Theme) -> Value {
+        serde_json::to_value(theme).unwrap()
+    }
+
+    #[test]
+    fn partial_dark_themes_keep_dark_colors() {
+        let theme = load(
+            "dark.json",
+            r##"{ "name": "Mine", "appearance": "dark", "node_fill": "#102030" }"##,
+        )
+        .unwrap();
+        let mut expected = written(&Theme::dark());
+        expected["name"] = json!("Mine");
+        expected["node_fill"] = json!("#102030FF");
+        assert_eq!(written(&theme), expected);
+    }
+
+    #[test]
+    fn themes_without_an_appearance_are_light() {
+        let theme = load("light.toml", "accent = \"#FF000080\"\n").unwrap();
+        let mut expected = written(&Theme::light());
+        expected["accent"] = json!("#FF000080");
+        assert_eq!(written(&theme), expected);
+    }
+
+    #[test]
+    fn bad_colors_are_reported() {
+        assert!(load("bad.json", r#"{ "accent": "red" }"#).is_err());
+    }
+}
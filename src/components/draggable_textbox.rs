@@ -1,13 +1,81 @@
 use super::text_field::TextField;
+use crate::common::board::{ImageData, ImageKind, NodeData};
+use crate::common::frame::{FrameData, FRAME_TITLE_HEIGHT};
+use crate::common::settings::LevelOfDetail;
+use crate::common::shape::NodeShape;
+use crate::common::style::NodeStyle;
+use crate::common::theme::Theme;
+use crate::common::Viewport;
+use gpui::prelude::FluentBuilder;
 use gpui::*;
+use std::{rc::Rc, sync::Arc};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Side in screen pixels of the resize handle in a box's bottom-right corner.
+const RESIZE_HANDLE_SIZE: f32 = 10.0;
+/// Font size in screen pixels of the label drawn at `LevelOfDetail::Title`.
+const TITLE_FONT_SIZE: f32 = 12.0;
+/// Graphemes of the text kept in the label at `LevelOfDetail::Title`.
+const TITLE_LENGTH: usize = 24;
+
+/// Image shown in place of the text field, along with the source gpui loads
+/// it from. The source is kept so embedded bytes aren't rehashed every frame.
+#[derive(Clone)]
+pub struct NodeImage {
+    pub data: ImageData,
+    pub source: ImageSource,
+}
+
+impl NodeImage {
+    pub fn new(data: ImageData) -> Self {
+        let source = match &data {
+            ImageData::Path { path } => {
+                ImageSource::Resource(Resource::Path(path.as_path().into()))
+            }
+            ImageData::Embedded { kind, bytes } => ImageSource::Image(Arc::new(Image::from_bytes(
+                image_format(*kind),
+                bytes.to_vec(),
+            ))),
+        };
+        Self { data, source }
+    }
+}
+
+fn image_format(kind: ImageKind) -> ImageFormat {
+    match kind {
+        ImageKind::Png => ImageFormat::Png,
+        ImageKind::Jpeg => ImageFormat::Jpeg,
+        ImageKind::Gif => ImageFormat::Gif,
+        ImageKind::Webp => ImageFormat::Webp,
+        ImageKind::Bmp => ImageFormat::Bmp,
+        ImageKind::Svg => ImageFormat::Svg,
+    }
+}
+
+pub fn image_kind(format: ImageFormat) -> Option<ImageKind> {
+    match format {
+        ImageFormat::Png => Some(ImageKind::Png),
+        ImageFormat::Jpeg => Some(ImageKind::Jpeg),
+        ImageFormat::Gif => Some(ImageKind::Gif),
+        ImageFormat::Webp => Some(ImageKind::Webp),
+        ImageFormat::Bmp => Some(ImageKind::Bmp),
+        ImageFormat::Svg => Some(ImageKind::Svg),
+        _ => None,
+    }
+}
 
 #[derive(Clone)]
 pub struct DraggableTextBox {
     pub textfield: Entity<TextField>,
     pub position: Point<Pixels>,
     pub size: Size<Pixels>,
-    pub is_dragging: bool,
     pub index: usize,
+    pub image: Option<NodeImage>,
+    pub shape: NodeShape,
+    pub style: NodeStyle,
+    pub frame: Option<FrameData>,
+    pub locked: bool,
+    pub hidden: bool,
 }
 
 impl DraggableTextBox {
@@ -18,47 +86,493 @@ impl DraggableTextBox {
         index: usize,
         cx: &mut Context<Self>,
     ) -> Self {
-        let textfield = cx.new(|cx| TextField::new(initial_text, cx));
+        // Clicks select and drag the box until editing starts
+        let textfield = cx.new(|cx| TextField::new(initial_text, cx).interactive(false));
         Self {
             textfield,
             position,
             size,
-            is_dragging: false,
             index,
+            image: None,
+            shape: NodeShape::default(),
+            style: NodeStyle::default(),
+            frame: None,
+            locked: false,
+            hidden: false,
+        }
+    }
+
+    pub fn from_data(data: &NodeData, index: usize, cx: &mut Context<Self>) -> Self {
+        let mut textbox = Self::new(
+            data.text.clone().into(),
+            point(px(data.x), px(data.y)),
+            size(px(data.width), px(data.height)),
+            index,
+            cx,
+        );
+        textbox.image = data.image.clone().map(NodeImage::new);
+        textbox.shape = data.shape;
+        textbox.frame = data.frame;
+        textbox.locked = data.locked;
+        textbox.hidden = data.hidden;
+        textbox.set_style(data.style.clone(), cx);
+        textbox
+    }
+
+    pub fn to_data(&self, cx: &App) -> NodeData {
+        NodeData {
+            text: self.textfield.read(cx).text().to_string(),
+            x: f32::from(self.position.x),
+            y: f32::from(self.position.y),
+            width: f32::from(self.size.width),
+            height: f32::from(self.size.height),
+            image: self.image.as_ref().map(|image| image.data.clone()),
+            shape: self.shape,
+            style: self.style.clone(),
+            frame: self.frame,
+            locked: self.locked,
+            hidden: self.hidden,
         }
     }
 
+    pub fn set_style(&mut self, style: NodeStyle, cx: &mut Context<Self>) {
+        let alignment = style.alignment;
+        self.textfield
+            .update(cx, |textfield, cx| textfield.set_alignment(alignment, cx));
+        self.style = style;
+        cx.notify();
+    }
+
+    /// Bounds of what is shown, which for a collapsed frame is its title bar.
     pub fn bounds(&self) -> Bounds<Pixels> {
+        match self.frame {
+            Some(FrameData { collapsed: true }) => Bounds::new(
+                self.position,
+                size(
+                    self.size.width,
+                    px(FRAME_TITLE_HEIGHT.min(f32::from(self.size.height))),
+                ),
+            ),
+            _ => Bounds::new(self.position, self.size),
+        }
+    }
+
+    /// Bounds including a collapsed frame's hidden body, which still decide
+    /// what the frame contains.
+    pub fn extent(&self) -> Bounds<Pixels> {
         Bounds::new(self.position, self.size)
     }
 
+    pub fn is_frame(&self) -> bool {
+        self.frame.is_some()
+    }
+
     pub fn overlaps(&self, other: &DraggableTextBox) -> bool {
-        let b1 = self.bounds();
-        let b2 = other.bounds();
+        self.shape
+            .overlaps(self.bounds(), other.shape, other.bounds())
+    }
+
+    pub fn contains(&self, point: Point<Pixels>) -> bool {
+        self.shape.contains(self.bounds(), point)
+    }
 
-        b1.origin.x < (b2.origin.x + b2.size.width)
-            && (b1.origin.x + b1.size.width) > b2.origin.x
-            && b1.origin.y < (b2.origin.y + b2.size.height)
-            && (b1.origin.y + b1.size.height) > b2.origin.y
+    /// Screen area of the resize handle in the bottom-right corner, which
+    /// lies outside ellipses and diamonds.
+    pub fn resize_handle(&self, viewport: &Viewport) -> Bounds<Pixels> {
+        let corner = viewport.transform_point(self.bounds().bottom_right());
+        Bounds::new(
+            point(
+                corner.x - px(RESIZE_HANDLE_SIZE),
+                corner.y - px(RESIZE_HANDLE_SIZE),
+            ),
+            size(px(RESIZE_HANDLE_SIZE), px(RESIZE_HANDLE_SIZE)),
+        )
     }
+
+    /// The element drawing this box at its screen position under `viewport`.
+    pub fn element(&self, viewport: &Viewport) -> NodeElement {
+        NodeElement {
+            textfield: self.textfield.clone(),
+            index: self.index,
+            image: self.image.as_ref().map(|image| image.source.clone()),
+            shape: self.shape,
+            style: self.style.clone(),
+            bounds: Bounds::new(
+                viewport.transform_point(self.position),
+                viewport.transform_size(self.size),
+            ),
+            zoom: viewport.zoom,
+            frame: self.frame,
+            selected: false,
+            focused: false,
+            dragging: false,
+            locked: false,
+            faded: false,
+            detail: LevelOfDetail::Full,
+            on_resize_start: None,
+            on_toggle_collapsed: None,
+        }
+    }
+}
+
+/// Paints the parts of a shape a styled div can't express, filling its parent.
+/// Ellipses and diamonds get their `outline` stroked along the shape, as a
+/// div border would be square.
+fn shape_canvas(shape: NodeShape, fill: Hsla, outline: Option<(Pixels, Hsla)>) -> impl IntoElement {
+    canvas(
+        |_, _, _| {},
+        move |bounds, _, window, cx| match shape {
+            NodeShape::Ellipse | NodeShape::Diamond => {
+                let polygon = shape.outline(bounds);
+                paint_polygon(&polygon, fill, window);
+                if let Some((width, color)) = outline {
+                    stroke_polygon(&polygon, width, color, window);
+                }
+            }
+            NodeShape::StickyNote => {
+                let fold =
+                    px(f32::from(bounds.size.width).min(f32::from(bounds.size.height)) * 0.15);
+                let corner = bounds.bottom_right();
+                paint_polygon(
+                    &[
+                        point(corner.x - fold, corner.y),
+                        point(corner.x, corner.y - fold),
+                        point(corner.x - fold, corner.y - fold),
+                    ],
+                    Theme::get(cx).sticky_note_fold,
+                    window,
+                );
+            }
+            NodeShape::Rectangle | NodeShape::Rounded => {}
+        },
+    )
+    .absolute()
+    .size_full()
 }
 
-impl Render for DraggableTextBox {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        div()
-            .absolute()
-            .left(self.position.x)
-            .top(self.position.y)
-            .w(self.size.width)
-            .h(self.size.height)
-            .bg(rgb(0x2D3142))
-            .text_color(rgb(0xFFFFFF))
-            .cursor(if self.is_dragging {
-                CursorStyle::ClosedHand
-            } else {
-                CursorStyle::OpenHand
+fn paint_polygon(points: &[Point<Pixels>], color: Hsla, window: &mut Window) {
+    let Some((first, rest)) = points.split_first() else {
+        return;
+    };
+    let mut path = Path::new(*first);
+    for point in rest {
+        path.line_to(*point);
+    }
+    window.paint_path(path, color);
+}
+
+/// Paints a closed polygon's edges `width` wide, each as a thin quad
+/// stretched past its ends so neighbouring edges meet at the corners.
+fn stroke_polygon(points: &[Point<Pixels>], width: Pixels, color: Hsla, window: &mut Window) {
+    let half_width = f32::from(width) / 2.0;
+    for (i, &start) in points.iter().enumerate() {
+        let end = points[(i + 1) % points.len()];
+        let (dx, dy) = (f32::from(end.x - start.x), f32::from(end.y - start.y));
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        let (ux, uy) = (dx / length * half_width, dy / length * half_width);
+        // Along the edge by (ux, uy), across it by (-uy, ux)
+        let corner = |p: Point<Pixels>, along: f32, across: f32| {
+            point(
+                p.x + px(ux * along - uy * across),
+                p.y + px(uy * along + ux * across),
+            )
+        };
+        paint_polygon(
+            &[
+                corner(start, -1.0, 1.0),
+                corner(end, 1.0, 1.0),
+                corner(end, 1.0, -1.0),
+                corner(start, -1.0, -1.0),
+            ],
+            color,
+            window,
+        );
+    }
+}
+
+/// Sets the width of all four borders, which the `border_*` helpers only
+/// offer in fixed steps.
+fn with_border_width<E: Styled>(mut element: E, width: Pixels) -> E {
+    let width = Some(AbsoluteLength::Pixels(width));
+    element.style().border_widths = EdgesRefinement {
+        top: width,
+        right: width,
+        bottom: width,
+        left: width,
+    };
+    element
+}
+
+/// Sets all four corner radii, which the `rounded_*` helpers only offer in
+/// fixed steps.
+fn with_corner_radius<E: Styled>(mut element: E, radius: Pixels) -> E {
+    let radius = Some(AbsoluteLength::Pixels(radius));
+    element.style().corner_radii = CornersRefinement {
+        top_left: radius,
+        top_right: radius,
+        bottom_right: radius,
+        bottom_left: radius,
+    };
+    element
+}
+
+/// A box as drawn on the canvas: its shape, style, image or text field and
+/// resize handle, placed at its screen position under the viewport. The
+/// canvas owns selection, focus and dragging and passes them in.
+#[derive(IntoElement)]
+pub struct NodeElement {
+    textfield: Entity<TextField>,
+    index: usize,
+    image: Option<ImageSource>,
+    shape: NodeShape,
+    style: NodeStyle,
+    bounds: Bounds<Pixels>,
+    zoom: f32,
+    frame: Option<FrameData>,
+    selected: bool,
+    focused: bool,
+    dragging: bool,
+    locked: bool,
+    /// Drawn see-through, for hidden boxes while hidden boxes are shown.
+    faded: bool,
+    detail: LevelOfDetail,
+    on_resize_start: Option<ResizeHandler>,
+    on_toggle_collapsed: Option<ToggleHandler>,
+}
+
+type ResizeHandler = Rc<dyn Fn(&MouseDownEvent, &mut Window, &mut App)>;
+type ToggleHandler = Rc<dyn Fn(&ClickEvent, &mut Window, &mut App)>;
+
+impl NodeElement {
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    pub fn dragging(mut self, dragging: bool) -> Self {
+        self.dragging = dragging;
+        self
+    }
+
+    pub fn locked(mut self, locked: bool) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    pub fn faded(mut self, faded: bool) -> Self {
+        self.faded = faded;
+        self
+    }
+
+    pub fn detail(mut self, detail: LevelOfDetail) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    pub fn on_resize_start(
+        mut self,
+        handler: impl Fn(&MouseDownEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_resize_start = Some(Rc::new(handler));
+        self
+    }
+
+    /// Called when the chevron in a frame's title bar is clicked.
+    pub fn on_toggle_collapsed(
+        mut self,
+        handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_toggle_collapsed = Some(Rc::new(handler));
+        self
+    }
+}
+
+impl RenderOnce for NodeElement {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = Theme::get(cx).clone();
+        let Self {
+            textfield,
+            index,
+            image,
+            shape,
+            style,
+            bounds,
+            zoom,
+            frame,
+            selected,
+            focused,
+            dragging,
+            locked,
+            faded,
+            detail,
+            on_resize_start,
+            on_toggle_collapsed,
+        } = self;
+        let size = bounds.size;
+
+        let (default_fill, default_text_color) = match (frame, shape) {
+            (Some(_), _) => (theme.frame_background, theme.frame_title),
+            (None, NodeShape::StickyNote) => (theme.sticky_note_fill, theme.sticky_note_text),
+            (None, _) => (theme.node_fill, theme.node_text),
+        };
+        let fill: Hsla = style.fill.map_or(default_fill, |fill| rgb(fill).into());
+        let text_color: Hsla = style
+            .text_color
+            .map_or(default_text_color, |color| rgb(color).into());
+        let corner_radius = style
+            .corner_radius
+            .map(|radius| px(radius * zoom))
+            .unwrap_or_else(|| shape.corner_radius(size));
+        // Frames always show an outline so their region stays visible
+        let (border_width, default_border_color) = match frame {
+            Some(_) => (style.border_width.max(1.0), theme.frame_border),
+            None => (style.border_width, fill),
+        };
+        let border_color: Hsla = style
+            .border_color
+            .map_or(default_border_color, |color| rgb(color).into());
+        // Ellipses and diamonds stroke their own outline, including the
+        // selection and focus highlight, in place of the square div border
+        let stroked = matches!(shape, NodeShape::Ellipse | NodeShape::Diamond) && frame.is_none();
+        let outline = if !stroked {
+            None
+        } else if focused {
+            Some((px(2.0), theme.accent))
+        } else if selected {
+            Some((px(1.0), theme.accent))
+        } else {
+            (border_width > 0.0).then(|| (px(border_width * zoom), border_color))
+        };
+        let text = node_text(textfield, detail, text_color, cx);
+
+        with_border_width(
+            with_corner_radius(div(), corner_radius),
+            px(if stroked { 0.0 } else { border_width * zoom }),
+        )
+        .border_color(border_color)
+        .absolute()
+        .left(bounds.origin.x)
+        .top(bounds.origin.y)
+        .w(size.width)
+        .h(size.height)
+        .when(!stroked, |div| div.bg(fill))
+        .text_color(text_color)
+        .text_size(px(style.font_size * zoom))
+        .when_some(style.font_family, |div, family| div.font_family(family))
+        .when(selected && !stroked, |div| {
+            div.border_1().border_color(theme.accent)
+        })
+        .when(focused && !stroked, |div| {
+            div.border_2().border_color(theme.accent)
+        })
+        .cursor(if locked {
+            CursorStyle::Arrow
+        } else if dragging {
+            CursorStyle::ClosedHand
+        } else {
+            CursorStyle::OpenHand
+        })
+        .when(faded, |div| div.opacity(0.4))
+        .id(("textbox", index))
+        .map(|div| match (frame, image) {
+            (Some(frame), _) => div.child(
+                gpui::div()
+                    .h(px((FRAME_TITLE_HEIGHT * zoom).min(f32::from(size.height))))
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(4.0 * zoom))
+                    .px(px(8.0 * zoom))
+                    .when_some(on_toggle_collapsed, |div, on_toggle_collapsed| {
+                        div.child(
+                            gpui::div()
+                                .id(("frame_collapse", index))
+                                .cursor(CursorStyle::PointingHand)
+                                .child(if frame.collapsed { "▸" } else { "▾" })
+                                // Keep the canvas from starting a drag under the chevron
+                                .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+                                .on_click(move |event, window, cx| {
+                                    on_toggle_collapsed(event, window, cx)
+                                }),
+                        )
+                    })
+                    .child(gpui::div().flex_1().overflow_hidden().child(text)),
+            ),
+            (None, Some(source)) => div
+                .child(shape_canvas(shape, fill, outline))
+                .child(img(source).size_full().object_fit(ObjectFit::Contain)),
+            (None, None) => {
+                let (inset_x, inset_y) = shape.text_inset();
+                div.child(shape_canvas(shape, fill, outline)).child(
+                    gpui::div()
+                        .size_full()
+                        .flex()
+                        .flex_col()
+                        .when(shape.centers_text(), |div| div.justify_center())
+                        .px(size.width * inset_x)
+                        .py(size.height * inset_y)
+                        .child(text),
+                )
+            }
+        })
+        .when_some(
+            on_resize_start
+                .filter(|_| !locked && !matches!(frame, Some(FrameData { collapsed: true }))),
+            |div, on_resize_start| {
+                div.child(
+                    gpui::div()
+                        .absolute()
+                        .right_0()
+                        .bottom_0()
+                        .size(px(RESIZE_HANDLE_SIZE))
+                        .cursor(CursorStyle::ResizeUpLeftDownRight)
+                        .on_mouse_down(MouseButton::Left, move |event, window, cx| {
+                            on_resize_start(event, window, cx)
+                        }),
+                )
+            },
+        )
+    }
+}
+
+/// A box's text at the given level of detail.
+fn node_text(
+    textfield: Entity<TextField>,
+    detail: LevelOfDetail,
+    text_color: Hsla,
+    cx: &App,
+) -> AnyElement {
+    match detail {
+        LevelOfDetail::Full => textfield.into_any_element(),
+        LevelOfDetail::Title => {
+            let title: String = textfield
+                .read(cx)
+                .text()
+                .graphemes(true)
+                .take(TITLE_LENGTH)
+                .collect();
+            div()
+                .text_size(px(TITLE_FONT_SIZE))
+                .overflow_hidden()
+                .whitespace_nowrap()
+                .child(title)
+                .into_any_element()
+        }
+        LevelOfDetail::Placeholder => div()
+            .when(!textfield.read(cx).text().is_empty(), |div| {
+                div.w(relative(0.7))
+                    .h(relative(0.3))
+                    .rounded_sm()
+                    .bg(text_color.opacity(0.4))
             })
-            .id(("textbox", self.index))
-            .child(self.textfield.clone())
+            .into_any_element(),
     }
 }
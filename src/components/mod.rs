@@ -1,3 +1,5 @@
 pub mod draggable_textbox;
+pub mod search_bar;
+pub mod style_inspector;
 pub mod text_field;
 pub mod viewport;
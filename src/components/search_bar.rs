@@ -0,0 +1,203 @@
+use super::style_inspector::button_base;
+use super::text_field::TextField;
+use super::viewport::{
+    CloseSearch, ReplaceAll, ReplaceOne, SearchNext, SearchPrevious, ToggleReplace,
+    ToggleSearchCaseSensitive, ToggleSearchRegex,
+};
+use crate::common::{search::SearchOptions, theme::Theme};
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+
+const BAR_WIDTH: f32 = 360.0;
+/// Boxes listed in the replace preview before the rest are summarized.
+const PREVIEW_LIMIT: usize = 6;
+/// Characters of each box's text shown in the replace preview.
+const PREVIEW_LENGTH: usize = 40;
+
+/// Search panel over the canvas: the query field, option toggles, match
+/// navigation and, when replacing, the replacement field with a preview of
+/// the boxes it changes. The buttons dispatch actions that the canvas
+/// handles.
+#[derive(IntoElement)]
+pub struct SearchBar {
+    field: Entity<TextField>,
+    options: SearchOptions,
+    status: SharedString,
+    error: Option<SharedString>,
+    replace_field: Option<Entity<TextField>>,
+    preview: Vec<(SharedString, SharedString)>,
+}
+
+impl SearchBar {
+    pub fn new(field: Entity<TextField>, options: SearchOptions) -> Self {
+        Self {
+            field,
+            options,
+            status: SharedString::default(),
+            error: None,
+            replace_field: None,
+            preview: Vec::new(),
+        }
+    }
+
+    /// Match count or position, shown next to the query.
+    pub fn status(mut self, status: impl Into<SharedString>) -> Self {
+        self.status = status.into();
+        self
+    }
+
+    /// Why the query couldn't be used, such as an invalid regex.
+    pub fn error(mut self, error: Option<SharedString>) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// Shows the replacement row, with each affected box's text before and
+    /// after replacing.
+    pub fn replace(
+        mut self,
+        field: Entity<TextField>,
+        preview: Vec<(SharedString, SharedString)>,
+    ) -> Self {
+        self.replace_field = Some(field);
+        self.preview = preview;
+        self
+    }
+}
+
+impl RenderOnce for SearchBar {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = Theme::get(cx);
+        let replacing = self.replace_field.is_some();
+
+        div()
+            .id("search_bar")
+            .key_context("SearchBar")
+            .absolute()
+            .top_2()
+            .left_2()
+            .w(px(BAR_WIDTH))
+            .p_2()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .bg(theme.panel_background)
+            .border_1()
+            .border_color(theme.panel_border)
+            .rounded_md()
+            .text_sm()
+            .text_color(theme.panel_text)
+            .occlude()
+            // Keep clicks in the bar from selecting or panning the canvas
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(toggle(
+                        "search_replace",
+                        if replacing { "▾" } else { "▸" },
+                        replacing,
+                        ToggleReplace,
+                        theme,
+                    ))
+                    .child(field_box(self.field, theme))
+                    .child(toggle(
+                        "search_case",
+                        "Aa",
+                        self.options.case_sensitive,
+                        ToggleSearchCaseSensitive,
+                        theme,
+                    ))
+                    .child(toggle(
+                        "search_regex",
+                        ".*",
+                        self.options.regex,
+                        ToggleSearchRegex,
+                        theme,
+                    ))
+                    .child(
+                        div()
+                            .min_w(px(56.0))
+                            .flex()
+                            .justify_center()
+                            .child(self.status),
+                    )
+                    .child(toggle("search_previous", "↑", false, SearchPrevious, theme))
+                    .child(toggle("search_next", "↓", false, SearchNext, theme))
+                    .child(toggle("search_close", "×", false, CloseSearch, theme)),
+            )
+            .when_some(self.error, |div, error| div.child(error))
+            .when_some(self.replace_field, |bar, field| {
+                bar.child(
+                    div()
+                        .key_context("ReplaceRow")
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap_1()
+                        .child(field_box(field, theme))
+                        .child(toggle("replace_one", "Replace", false, ReplaceOne, theme))
+                        .child(toggle("replace_all", "All", false, ReplaceAll, theme)),
+                )
+                .child(preview_list(&self.preview, theme))
+            })
+    }
+}
+
+fn field_box(field: Entity<TextField>, theme: &Theme) -> Div {
+    div()
+        .flex_1()
+        .px_1()
+        .overflow_hidden()
+        .border_1()
+        .border_color(theme.panel_border)
+        .rounded_sm()
+        .child(field)
+}
+
+fn preview_list(preview: &[(SharedString, SharedString)], theme: &Theme) -> Div {
+    let hidden = preview.len().saturating_sub(PREVIEW_LIMIT);
+    div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .pt_1()
+        .border_t_1()
+        .border_color(theme.panel_border)
+        .text_xs()
+        .children(preview.iter().take(PREVIEW_LIMIT).map(|(before, after)| {
+            div()
+                .flex()
+                .flex_col()
+                .child(div().line_through().opacity(0.6).child(shorten(before)))
+                .child(shorten(after))
+        }))
+        .when(hidden > 0, |div| {
+            div.child(format!("and {hidden} more boxes"))
+        })
+}
+
+/// First line of `text`, cut to fit the preview.
+fn shorten(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    let mut chars = line.chars();
+    let mut short: String = chars.by_ref().take(PREVIEW_LENGTH).collect();
+    if chars.next().is_some() || line.len() < text.trim_end().len() {
+        short.push('…');
+    }
+    short
+}
+
+fn toggle(
+    id: &'static str,
+    label: &'static str,
+    active: bool,
+    action: impl Action + Clone,
+    theme: &Theme,
+) -> Stateful<Div> {
+    button_base(id, label, active, theme)
+        .on_click(move |_, window, cx| window.dispatch_action(action.boxed_clone(), cx))
+}
@@ -0,0 +1,266 @@
+use super::viewport::{CopyStyle, PasteStyle};
+use crate::common::{
+    style::{NodeStyle, StyleEdit, TextAlignment},
+    theme::Theme,
+};
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use std::rc::Rc;
+
+const PANEL_WIDTH: f32 = 220.0;
+const SWATCH_SIZE: f32 = 18.0;
+/// Colors offered for fills, borders and text.
+const PALETTE: [u32; 8] = [
+    0x2D3142, 0xFFFFFF, 0xEF476F, 0xFFD166, 0x06D6A0, 0x118AB2, 0x8338EC, 0x000000,
+];
+const FONT_FAMILIES: [(&str, Option<&str>); 4] = [
+    ("Default", None),
+    ("Sans", Some("Helvetica")),
+    ("Serif", Some("Times New Roman")),
+    ("Mono", Some("Courier New")),
+];
+
+type EditHandler = Rc<dyn Fn(&StyleEdit, &mut Window, &mut App)>;
+
+/// Panel for editing the style of the selected boxes. Edits are reported
+/// through `on_edit`; the owner applies them to its selection.
+#[derive(IntoElement)]
+pub struct StyleInspector {
+    style: NodeStyle,
+    on_edit: EditHandler,
+}
+
+impl StyleInspector {
+    pub fn new(
+        style: NodeStyle,
+        on_edit: impl Fn(&StyleEdit, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        Self {
+            style,
+            on_edit: Rc::new(on_edit),
+        }
+    }
+}
+
+impl RenderOnce for StyleInspector {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let style = self.style;
+        let on_edit = self.on_edit;
+        let theme = Theme::get(cx);
+
+        div()
+            .id("style_inspector")
+            .absolute()
+            .top_2()
+            .right_2()
+            .w(px(PANEL_WIDTH))
+            .p_2()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .bg(theme.panel_background)
+            .border_1()
+            .border_color(theme.panel_border)
+            .rounded_md()
+            .text_sm()
+            .text_color(theme.panel_text)
+            .occlude()
+            // Keep clicks in the panel from selecting or panning the canvas
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .child(color_row(
+                "fill",
+                "Fill",
+                style.fill,
+                StyleEdit::Fill,
+                theme,
+                &on_edit,
+            ))
+            .child(color_row(
+                "border_color",
+                "Border",
+                style.border_color,
+                StyleEdit::BorderColor,
+                theme,
+                &on_edit,
+            ))
+            .child(color_row(
+                "text_color",
+                "Text",
+                style.text_color,
+                StyleEdit::TextColor,
+                theme,
+                &on_edit,
+            ))
+            .child(stepper_row(
+                "border_width",
+                "Border width",
+                format!("{}", style.border_width).into(),
+                StyleEdit::BorderWidth((style.border_width - 1.0).max(0.0)),
+                StyleEdit::BorderWidth(style.border_width + 1.0),
+                theme,
+                &on_edit,
+            ))
+            .child(stepper_row(
+                "corner_radius",
+                "Corner radius",
+                style
+                    .corner_radius
+                    .map_or("auto".into(), |radius| format!("{radius}").into()),
+                StyleEdit::CornerRadius(
+                    style
+                        .corner_radius
+                        .map(|radius| radius - 2.0)
+                        .filter(|radius| *radius >= 0.0),
+                ),
+                StyleEdit::CornerRadius(Some(style.corner_radius.unwrap_or(0.0) + 2.0)),
+                theme,
+                &on_edit,
+            ))
+            .child(stepper_row(
+                "font_size",
+                "Font size",
+                format!("{}", style.font_size).into(),
+                StyleEdit::FontSize(style.font_size - 1.0),
+                StyleEdit::FontSize(style.font_size + 1.0),
+                theme,
+                &on_edit,
+            ))
+            .child(
+                row("Font").children(FONT_FAMILIES.iter().enumerate().map(
+                    |(idx, (label, family))| {
+                        let family = family.map(str::to_string);
+                        button(
+                            ("font_family", idx),
+                            *label,
+                            style.font_family == family,
+                            StyleEdit::FontFamily(family),
+                            theme,
+                            &on_edit,
+                        )
+                    },
+                )),
+            )
+            .child(
+                row("Align").children(
+                    [
+                        ("Left", TextAlignment::Left),
+                        ("Center", TextAlignment::Center),
+                        ("Right", TextAlignment::Right),
+                    ]
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, (label, alignment))| {
+                        button(
+                            ("alignment", idx),
+                            label,
+                            style.alignment == alignment,
+                            StyleEdit::Alignment(alignment),
+                            theme,
+                            &on_edit,
+                        )
+                    }),
+                ),
+            )
+            .child(
+                row("Style")
+                    .child(action_button("copy_style", "Copy", CopyStyle, theme))
+                    .child(action_button("paste_style", "Paste", PasteStyle, theme)),
+            )
+    }
+}
+
+fn row(label: &'static str) -> Div {
+    div()
+        .flex()
+        .flex_row()
+        .flex_wrap()
+        .items_center()
+        .gap_1()
+        .child(div().w(px(80.0)).child(label))
+}
+
+fn color_row(
+    id: &'static str,
+    label: &'static str,
+    current: Option<u32>,
+    edit: fn(Option<u32>) -> StyleEdit,
+    theme: &Theme,
+    on_edit: &EditHandler,
+) -> Div {
+    let swatches = std::iter::once(None)
+        .chain(PALETTE.into_iter().map(Some))
+        .enumerate()
+        .map(move |(idx, color)| {
+            let on_edit = on_edit.clone();
+            div()
+                .id((id, idx))
+                .size(px(SWATCH_SIZE))
+                .rounded_sm()
+                .border_1()
+                .border_color(theme.panel_border)
+                .when(current == color, |swatch| {
+                    swatch.border_2().border_color(theme.accent)
+                })
+                .map(|swatch| match color {
+                    Some(color) => swatch.bg(rgb(color)),
+                    // Unset, so the box uses its shape's default
+                    None => swatch.flex().justify_center().items_center().child("–"),
+                })
+                .on_click(move |_, window, cx| on_edit(&edit(color), window, cx))
+        });
+    row(label).children(swatches)
+}
+
+fn stepper_row(
+    id: &'static str,
+    label: &'static str,
+    value: SharedString,
+    decrease: StyleEdit,
+    increase: StyleEdit,
+    theme: &Theme,
+    on_edit: &EditHandler,
+) -> Div {
+    row(label)
+        .child(button((id, 0), "-", false, decrease, theme, on_edit))
+        .child(div().min_w(px(32.0)).flex().justify_center().child(value))
+        .child(button((id, 1), "+", false, increase, theme, on_edit))
+}
+
+pub(super) fn button_base(
+    id: impl Into<ElementId>,
+    label: &'static str,
+    active: bool,
+    theme: &Theme,
+) -> Stateful<Div> {
+    div()
+        .id(id)
+        .px_1()
+        .rounded_sm()
+        .border_1()
+        .border_color(theme.panel_border)
+        .when(active, |button| button.bg(theme.panel_button_active))
+        .hover(|style| style.bg(theme.panel_button_hover))
+        .child(label)
+}
+
+fn button(
+    id: impl Into<ElementId>,
+    label: &'static str,
+    active: bool,
+    edit: StyleEdit,
+    theme: &Theme,
+    on_edit: &EditHandler,
+) -> Stateful<Div> {
+    let on_edit = on_edit.clone();
+    button_base(id, label, active, theme).on_click(move |_, window, cx| on_edit(&edit, window, cx))
+}
+
+fn action_button(
+    id: &'static str,
+    label: &'static str,
+    action: impl Action + Clone,
+    theme: &Theme,
+) -> Stateful<Div> {
+    button_base(id, label, false, theme)
+        .on_click(move |_, window, cx| window.dispatch_action(action.boxed_clone(), cx))
+}
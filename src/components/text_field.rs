@@ -1,7 +1,26 @@
+use crate::common::{
+    settings::{CaretStyle, Settings},
+    style::TextAlignment,
+    theme::Theme,
+};
+use gpui::prelude::FluentBuilder;
 use gpui::*;
-use std::ops::Range;
+use regex::Regex;
+use std::{ops::Range, rc::Rc};
 use unicode_segmentation::*;
 
+/// Font size in screen pixels below which text is too small to read, so it
+/// is drawn as a bar instead of being shaped.
+const GREEKING_THRESHOLD: f32 = 4.0;
+/// Average glyph width as a fraction of the font size, used to size the bar
+/// drawn in place of greeked text.
+const GREEKED_GLYPH_WIDTH: f32 = 0.5;
+/// Width of a block or underline caret past the last character, as a
+/// fraction of the font size.
+const END_CARET_WIDTH: f32 = 0.5;
+/// Drawn in place of each character of a masked field.
+const MASK_CHAR: char = '•';
+
 actions!(
     text_field,
     [
@@ -18,10 +37,43 @@ actions!(
         Paste,
         Cut,
         Copy,
+        Submit,
     ]
 );
 
+/// What a `TextField` reports to the views that subscribe to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextFieldEvent {
+    /// The text was edited, by typing or through the API.
+    Changed,
+    Focused,
+    Blurred,
+    /// Enter was pressed in the field.
+    Submitted,
+    /// Text dragged out of `range` was dropped outside the field, at the
+    /// window position `position`. The owner decides where it lands and,
+    /// unless `copy` is set, removes it from this field.
+    TextDropped {
+        range: Range<usize>,
+        position: Point<Pixels>,
+        copy: bool,
+    },
+}
+
+/// Restricts what can be typed or pasted into a field.
 #[derive(Clone)]
+pub enum InputFilter {
+    /// Drops everything but the digits 0-9 from inserted text.
+    Numeric,
+    /// Rejects edits that leave text not matching the pattern. Anchor it and
+    /// allow partial input, like `^[0-9a-f]{0,6}$`, or the field can't be
+    /// typed into one character at a time.
+    Regex(Regex),
+    /// Maps inserted text to what's actually inserted, or `None` to reject
+    /// the edit.
+    Custom(Rc<dyn Fn(&str) -> Option<String>>),
+}
+
 pub struct TextField {
     content: SharedString,
     placeholder: SharedString,
@@ -29,9 +81,50 @@ pub struct TextField {
     selection_reversed: bool,
     marked_range: Option<Range<usize>>,
     last_layout: Option<ShapedLine>,
+    /// What `last_layout` was shaped from, so an unchanged line isn't
+    /// reshaped on the next frame.
+    last_layout_key: Option<LineKey>,
     last_bounds: Option<Bounds<Pixels>>,
+    /// How far text wider than the field is scrolled left to keep the
+    /// caret in view.
+    scroll_offset: Pixels,
     is_selecting: bool,
+    /// Selected text being dragged somewhere else.
+    text_drag: Option<Range<usize>>,
+    /// Where dragged text would land in this field if dropped now.
+    drop_offset: Option<usize>,
+    /// Whether the blinking caret is in its visible phase.
+    caret_visible: bool,
+    /// Tracked from focus events, so unfocused fields don't keep blink
+    /// timers running.
+    focused: bool,
+    /// Bumped whenever blinking restarts or stops, so timers from before
+    /// know to give up.
+    blink_epoch: usize,
     focus_handle: FocusHandle,
+    alignment: TextAlignment,
+    /// Ranges marked by a search, and which of them is the current match.
+    highlights: Vec<Range<usize>>,
+    active_highlight: Option<usize>,
+    /// Whether the field takes clicks and focus from the mouse. Fields in
+    /// canvas boxes turn this off until editing starts, so clicks select and
+    /// drag the box instead.
+    interactive: bool,
+    /// Selectable and copyable, but not editable by the user.
+    read_only: bool,
+    /// Most graphemes the user can enter.
+    max_length: Option<usize>,
+    filters: Vec<InputFilter>,
+    /// Draws a bullet per character instead of the text, and keeps it off
+    /// the clipboard.
+    masked: bool,
+    /// Whether a masked field shows a button that reveals the text while
+    /// it's held.
+    reveal_toggle: bool,
+    revealed: bool,
+    /// Emit `Focused` and `Blurred`; set up the first time the field is
+    /// focused, through `focus` or a click, as that needs a window.
+    focus_subscriptions: Vec<Subscription>,
 }
 
 impl TextField {
@@ -43,9 +136,210 @@ impl TextField {
             selection_reversed: false,
             marked_range: None,
             last_layout: None,
+            last_layout_key: None,
             last_bounds: None,
+            scroll_offset: px(0.0),
             is_selecting: false,
+            text_drag: None,
+            drop_offset: None,
+            caret_visible: true,
+            focused: false,
+            blink_epoch: 0,
             focus_handle: cx.focus_handle(),
+            alignment: TextAlignment::default(),
+            highlights: Vec::new(),
+            active_highlight: None,
+            interactive: true,
+            read_only: false,
+            max_length: None,
+            filters: Vec::new(),
+            masked: false,
+            reveal_toggle: false,
+            revealed: false,
+            focus_subscriptions: Vec::new(),
+        }
+    }
+
+    /// Focuses the field, as clicking it does.
+    pub fn focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.watch_focus(window, cx);
+        window.focus(&self.focus_handle);
+    }
+
+    /// Starts emitting focus events, ahead of the field's first focus.
+    /// Focus changes are reported when the window next draws, so this
+    /// still catches the focus about to be given.
+    fn watch_focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.focus_subscriptions.is_empty() {
+            return;
+        }
+        let focus_handle = self.focus_handle.clone();
+        self.focus_subscriptions = vec![
+            cx.on_focus(&focus_handle, window, |this, _, cx| {
+                this.focused = true;
+                this.restart_blink(cx);
+                cx.emit(TextFieldEvent::Focused)
+            }),
+            cx.on_blur(&focus_handle, window, |this, _, cx| {
+                this.focused = false;
+                this.stop_blink(cx);
+                cx.emit(TextFieldEvent::Blurred)
+            }),
+        ];
+    }
+
+    /// Text shown while the field is empty.
+    pub fn placeholder(mut self, placeholder: impl Into<SharedString>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    pub fn set_interactive(&mut self, interactive: bool, cx: &mut Context<Self>) {
+        self.interactive = interactive;
+        self.is_selecting = false;
+        self.text_drag = None;
+        self.drop_offset = None;
+        cx.notify();
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Caps the text the user can enter at `graphemes` characters; longer
+    /// input is cut short.
+    pub fn max_length(mut self, graphemes: usize) -> Self {
+        self.max_length = Some(graphemes);
+        self
+    }
+
+    /// Adds a filter that typed and pasted text passes through, after the
+    /// ones added before it.
+    pub fn filter(mut self, filter: InputFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Hides the text behind bullets, as for passwords and tokens.
+    pub fn masked(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        self
+    }
+
+    /// Adds a button to a masked field that shows the text while held.
+    pub fn reveal_toggle(mut self, reveal_toggle: bool) -> Self {
+        self.reveal_toggle = reveal_toggle;
+        self
+    }
+
+    /// Shows or hides a masked field's text.
+    pub fn set_revealed(&mut self, revealed: bool, cx: &mut Context<Self>) {
+        self.revealed = revealed;
+        cx.notify();
+    }
+
+    pub fn set_alignment(&mut self, alignment: TextAlignment, cx: &mut Context<Self>) {
+        self.alignment = alignment;
+        cx.notify();
+    }
+
+    pub fn set_highlights(
+        &mut self,
+        highlights: Vec<Range<usize>>,
+        active: Option<usize>,
+        cx: &mut Context<Self>,
+    ) {
+        self.highlights = highlights;
+        self.active_highlight = active;
+        cx.notify();
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The field's text.
+    pub fn text(&self) -> &SharedString {
+        &self.content
+    }
+
+    /// Replaces all of the text, leaving the cursor at its end.
+    pub fn set_text(&mut self, text: impl Into<SharedString>, cx: &mut Context<Self>) {
+        let len = self.content.len();
+        self.replace_range(0..len, &text.into(), cx);
+    }
+
+    /// Replaces the UTF-8 byte `range` of the text with `new_text`, leaving
+    /// the cursor after it.
+    pub fn replace_range(&mut self, range: Range<usize>, new_text: &str, cx: &mut Context<Self>) {
+        let range = self.clamp_offset(range.start)..self.clamp_offset(range.end);
+        self.content =
+            (self.content[0..range.start].to_owned() + new_text + &self.content[range.end..])
+                .into();
+        self.selected_range = range.start + new_text.len()..range.start + new_text.len();
+        self.selection_reversed = false;
+        self.marked_range.take();
+        // Edits shift the text under any search highlights
+        self.highlights.clear();
+        self.restart_blink(cx);
+        cx.emit(TextFieldEvent::Changed);
+        cx.notify();
+    }
+
+    /// Inserts `text` at the UTF-8 byte `offset` as if the user had typed
+    /// it there, leaving what was inserted selected. Returns `false` if
+    /// read-only mode or the filters reject it.
+    pub fn insert_input(&mut self, offset: usize, text: &str, cx: &mut Context<Self>) -> bool {
+        let offset = self.clamp_offset(offset);
+        let Some(text) = self.accept_input(&(offset..offset), text) else {
+            return false;
+        };
+        self.replace_range(offset..offset, &text, cx);
+        self.select_range(offset..offset + text.len(), cx);
+        true
+    }
+
+    /// Replaces the selection with `text`, or inserts it at the cursor.
+    pub fn insert_at_cursor(&mut self, text: &str, cx: &mut Context<Self>) {
+        self.replace_range(self.selected_range.clone(), text, cx);
+    }
+
+    /// The selected UTF-8 byte range, empty when there's only a cursor.
+    pub fn selected_range(&self) -> Range<usize> {
+        self.selected_range.clone()
+    }
+
+    /// Selects the UTF-8 byte `range`, with the cursor at its end. Offsets
+    /// past the end or inside a character are pulled back to the nearest
+    /// character boundary.
+    pub fn select_range(&mut self, range: Range<usize>, cx: &mut Context<Self>) {
+        let (start, end) = (self.clamp_offset(range.start), self.clamp_offset(range.end));
+        self.selected_range = start.min(end)..start.max(end);
+        self.selection_reversed = end < start;
+        self.marked_range.take();
+        self.update_primary_selection(cx);
+        self.restart_blink(cx);
+        cx.notify();
+    }
+
+    /// UTF-8 byte offset of the character boundary nearest a window
+    /// position, as last laid out.
+    pub fn offset_for_position(&self, position: Point<Pixels>) -> usize {
+        self.index_for_mouse_position(position)
+    }
+
+    /// UTF-8 byte offset of the cursor, the moving end of the selection.
+    pub fn cursor_offset(&self) -> usize {
+        if self.selection_reversed {
+            self.selected_range.start
+        } else {
+            self.selected_range.end
         }
     }
 
@@ -87,6 +381,9 @@ impl TextField {
     }
 
     fn backspace(&mut self, _: &Backspace, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
         if self.selected_range.is_empty() {
             self.select_to(self.previous_boundary(self.cursor_offset()), cx)
         }
@@ -94,6 +391,9 @@ impl TextField {
     }
 
     fn delete(&mut self, _: &Delete, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
         if self.selected_range.is_empty() {
             self.select_to(self.next_boundary(self.cursor_offset()), cx)
         }
@@ -103,28 +403,163 @@ impl TextField {
     fn on_mouse_down(
         &mut self,
         event: &MouseDownEvent,
-        _window: &mut Window,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        // The click focuses the field
+        self.watch_focus(window, cx);
+        // Selecting text shouldn't also drag the box or pan the canvas
+        cx.stop_propagation();
+        let offset = self.index_for_mouse_position(event.position);
+        // Pressing inside the selection picks it up to drag it elsewhere
+        // rather than starting a new selection
+        if !event.modifiers.shift && !self.masked && self.selected_range.contains(&offset) {
+            self.text_drag = Some(self.selected_range.clone());
+            self.drop_offset = Some(offset);
+            return;
+        }
+
         self.is_selecting = true;
 
         if event.modifiers.shift {
-            self.select_to(self.index_for_mouse_position(event.position), cx);
+            self.select_to(offset, cx);
         } else {
-            self.move_to(self.index_for_mouse_position(event.position), cx)
+            self.move_to(offset, cx)
         }
     }
 
-    fn on_mouse_up(&mut self, _: &MouseUpEvent, _window: &mut Window, _: &mut Context<Self>) {
-        self.is_selecting = false;
+    fn on_mouse_up(&mut self, event: &MouseUpEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.is_selecting {
+            self.is_selecting = false;
+            self.update_primary_selection(cx);
+        }
+        let Some(range) = self.text_drag.take() else {
+            return;
+        };
+        let offset = self
+            .drop_offset
+            .take()
+            .unwrap_or_else(|| self.index_for_mouse_position(event.position));
+        if (range.start..=range.end).contains(&offset) {
+            // Released where it started, so it was just a click
+            self.move_to(offset, cx);
+        } else {
+            self.drop_text(range, offset, is_copy_drag(&event.modifiers), cx);
+        }
     }
 
-    fn on_mouse_move(&mut self, event: &MouseMoveEvent, _: &mut Window, cx: &mut Context<Self>) {
+    fn on_mouse_up_out(
+        &mut self,
+        event: &MouseUpEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
         if self.is_selecting {
+            self.is_selecting = false;
+            self.update_primary_selection(cx);
+        }
+        if let Some(range) = self.text_drag.take() {
+            self.drop_offset = None;
+            cx.emit(TextFieldEvent::TextDropped {
+                range,
+                position: event.position,
+                copy: is_copy_drag(&event.modifiers),
+            });
+            cx.notify();
+        }
+    }
+
+    fn on_mouse_move(&mut self, event: &MouseMoveEvent, _: &mut Window, cx: &mut Context<Self>) {
+        if self.text_drag.is_some() {
+            self.drop_offset = self
+                .last_bounds
+                .filter(|bounds| bounds.contains(&event.position))
+                .map(|_| self.index_for_mouse_position(event.position));
+            cx.notify();
+        } else if self.is_selecting {
             self.select_to(self.index_for_mouse_position(event.position), cx);
         }
     }
 
+    /// Moves or copies the text in `range` to `offset`, leaving it selected.
+    fn drop_text(
+        &mut self,
+        range: Range<usize>,
+        offset: usize,
+        copy: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let text = self.content[range.clone()].to_string();
+        let (text, offset) = if copy {
+            let Some(text) = self.accept_input(&(offset..offset), &text) else {
+                return;
+            };
+            (text, offset)
+        } else {
+            if self.read_only {
+                return;
+            }
+            self.replace_range(range.clone(), "", cx);
+            let offset = if offset > range.end {
+                offset - range.len()
+            } else {
+                offset
+            };
+            (text, offset)
+        };
+        self.replace_range(offset..offset, &text, cx);
+        self.select_range(offset..offset + text.len(), cx);
+    }
+
+    /// Pastes the primary selection where the middle button was pressed.
+    fn on_middle_mouse_down(
+        &mut self,
+        event: &MouseDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(text) = read_primary_selection(cx) else {
+            return;
+        };
+        cx.stop_propagation();
+        self.move_to(self.index_for_mouse_position(event.position), cx);
+        self.replace_text_in_range(None, &text.replace("\n", " "), window, cx);
+    }
+
+    /// Pastes the primary selection at a window position, as a middle click
+    /// on the field does. Returns `false` if there's nothing to paste or
+    /// the field rejects it.
+    pub fn paste_primary_at(&mut self, position: Point<Pixels>, cx: &mut Context<Self>) -> bool {
+        let Some(text) = read_primary_selection(cx) else {
+            return false;
+        };
+        let offset = self.index_for_mouse_position(position);
+        let Some(text) = self.accept_input(&(offset..offset), &text.replace("\n", " ")) else {
+            return false;
+        };
+        self.replace_range(offset..offset, &text, cx);
+        true
+    }
+
+    /// Offers the selected text as the primary selection, where the
+    /// platform has one.
+    fn update_primary_selection(&self, cx: &mut Context<Self>) {
+        if !self.selected_range.is_empty() && !self.masked {
+            write_primary_selection(self.content[self.selected_range.clone()].to_string(), cx);
+        }
+    }
+
+    /// Opens the system emoji and symbol picker. Only macOS has one, so
+    /// the action is bound there alone and does nothing elsewhere.
+    fn show_character_palette(
+        &mut self,
+        _: &ShowCharacterPalette,
+        window: &mut Window,
+        _: &mut Context<Self>,
+    ) {
+        window.show_character_palette();
+    }
+
     fn paste(&mut self, _: &Paste, window: &mut Window, cx: &mut Context<Self>) {
         if let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) {
             self.replace_text_in_range(None, &text.replace("\n", " "), window, cx);
@@ -132,7 +567,7 @@ impl TextField {
     }
 
     fn copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
-        if !self.selected_range.is_empty() {
+        if !self.selected_range.is_empty() && !self.masked {
             cx.write_to_clipboard(ClipboardItem::new_string(
                 (&self.content[self.selected_range.clone()]).to_string(),
             ));
@@ -140,7 +575,7 @@ impl TextField {
     }
 
     fn cut(&mut self, _: &Cut, window: &mut Window, cx: &mut Context<Self>) {
-        if !self.selected_range.is_empty() {
+        if !self.selected_range.is_empty() && !self.masked {
             cx.write_to_clipboard(ClipboardItem::new_string(
                 (&self.content[self.selected_range.clone()]).to_string(),
             ));
@@ -148,17 +583,75 @@ impl TextField {
         }
     }
 
+    fn submit(&mut self, _: &Submit, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(TextFieldEvent::Submitted);
+        // Let the enclosing view's own enter binding run as well
+        cx.propagate();
+    }
+
     fn move_to(&mut self, offset: usize, cx: &mut Context<Self>) {
         self.selected_range = offset..offset;
+        self.restart_blink(cx);
         cx.notify()
     }
 
-    fn cursor_offset(&self) -> usize {
-        if self.selection_reversed {
-            self.selected_range.start
-        } else {
-            self.selected_range.end
+    /// Shows the caret solid, and starts it blinking again after a pause.
+    fn restart_blink(&mut self, cx: &mut Context<Self>) {
+        self.caret_visible = true;
+        self.blink_epoch += 1;
+        self.schedule_blink(self.blink_epoch, cx);
+    }
+
+    fn stop_blink(&mut self, cx: &mut Context<Self>) {
+        self.caret_visible = true;
+        self.blink_epoch += 1;
+        cx.notify();
+    }
+
+    fn schedule_blink(&self, epoch: usize, cx: &mut Context<Self>) {
+        let caret = &Settings::get(cx).caret;
+        if !caret.blink || !self.focused {
+            return;
+        }
+        let interval = caret.blink_interval();
+        cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(interval).await;
+            this.update(&mut cx, |this, cx| this.blink(epoch, cx)).ok();
+        })
+        .detach();
+    }
+
+    fn blink(&mut self, epoch: usize, cx: &mut Context<Self>) {
+        if epoch != self.blink_epoch {
+            return;
+        }
+        self.caret_visible = !self.caret_visible;
+        cx.notify();
+        self.schedule_blink(epoch, cx);
+    }
+
+    /// What user input replacing `range` with `new_text` actually inserts
+    /// once read-only mode, filters and the length cap have had their say,
+    /// or `None` if the edit is rejected.
+    fn accept_input(&self, range: &Range<usize>, new_text: &str) -> Option<String> {
+        if self.read_only {
+            return None;
         }
+        filter_input(
+            &self.content,
+            range,
+            new_text,
+            self.max_length,
+            &self.filters,
+        )
+    }
+
+    fn clamp_offset(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.content.len());
+        while !self.content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
     }
 
     fn index_for_mouse_position(&self, position: Point<Pixels>) -> usize {
@@ -176,7 +669,41 @@ impl TextField {
         if position.y > bounds.bottom() {
             return self.content.len();
         }
-        line.closest_index_for_x(position.x - bounds.left())
+        self.offset_from_display(
+            line.closest_index_for_x(position.x - bounds.left() + self.scroll_offset),
+        )
+    }
+
+    fn is_masked(&self) -> bool {
+        self.masked && !self.revealed
+    }
+
+    /// The text as drawn, with a bullet per grapheme while masked.
+    fn display_text(&self) -> SharedString {
+        if self.is_masked() {
+            let bullets: String = self.content.graphemes(true).map(|_| MASK_CHAR).collect();
+            bullets.into()
+        } else {
+            self.content.clone()
+        }
+    }
+
+    /// Where a UTF-8 offset into the content falls in the drawn text.
+    fn offset_to_display(&self, offset: usize) -> usize {
+        if self.is_masked() {
+            masked_offset(&self.content, offset)
+        } else {
+            offset
+        }
+    }
+
+    /// The content offset drawn at a UTF-8 offset into the drawn text.
+    fn offset_from_display(&self, offset: usize) -> usize {
+        if self.is_masked() {
+            unmasked_offset(&self.content, offset)
+        } else {
+            offset
+        }
     }
 
     fn select_to(&mut self, offset: usize, cx: &mut Context<Self>) {
@@ -189,6 +716,11 @@ impl TextField {
             self.selection_reversed = !self.selection_reversed;
             self.selected_range = self.selected_range.end..self.selected_range.start;
         }
+        // Mouse selections are offered once the button is released
+        if !self.is_selecting {
+            self.update_primary_selection(cx);
+        }
+        self.restart_blink(cx);
         cx.notify()
     }
 
@@ -254,6 +786,10 @@ impl EntityInputHandler for TextField {
         _window: &mut Window,
         _cx: &mut Context<Self>,
     ) -> Option<String> {
+        // Input methods don't get to read a hidden secret
+        if self.is_masked() {
+            return None;
+        }
         let range = self.range_from_utf16(&range_utf16);
         actual_range.replace(self.range_to_utf16(&range));
         Some(self.content[range].to_string())
@@ -297,13 +833,10 @@ impl EntityInputHandler for TextField {
             .map(|range_utf16| self.range_from_utf16(range_utf16))
             .or(self.marked_range.clone())
             .unwrap_or(self.selected_range.clone());
-
-        self.content =
-            (self.content[0..range.start].to_owned() + new_text + &self.content[range.end..])
-                .into();
-        self.selected_range = range.start + new_text.len()..range.start + new_text.len();
-        self.marked_range.take();
-        cx.notify();
+        let Some(new_text) = self.accept_input(&range, new_text) else {
+            return;
+        };
+        self.replace_range(range, &new_text, cx);
     }
 
     fn replace_and_mark_text_in_range(
@@ -314,6 +847,10 @@ impl EntityInputHandler for TextField {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        // Composition is filtered when it's committed
+        if self.read_only {
+            return;
+        }
         let range = range_utf16
             .as_ref()
             .map(|range_utf16| self.range_from_utf16(range_utf16))
@@ -330,6 +867,8 @@ impl EntityInputHandler for TextField {
             .map(|new_range| new_range.start + range.start..new_range.end + range.end)
             .unwrap_or_else(|| range.start + new_text.len()..range.start + new_text.len());
 
+        self.restart_blink(cx);
+        cx.emit(TextFieldEvent::Changed);
         cx.notify();
     }
 
@@ -342,13 +881,20 @@ impl EntityInputHandler for TextField {
     ) -> Option<Bounds<Pixels>> {
         let last_layout = self.last_layout.as_ref()?;
         let range = self.range_from_utf16(&range_utf16);
+        // The last painted line bounds include the alignment offset, but not
+        // the scroll
+        let bounds = self.last_bounds.unwrap_or(bounds);
+        let bounds = Bounds::new(
+            point(bounds.left() - self.scroll_offset, bounds.top()),
+            bounds.size,
+        );
         Some(Bounds::from_corners(
             point(
-                bounds.left() + last_layout.x_for_index(range.start),
+                bounds.left() + last_layout.x_for_index(self.offset_to_display(range.start)),
                 bounds.top(),
             ),
             point(
-                bounds.left() + last_layout.x_for_index(range.end),
+                bounds.left() + last_layout.x_for_index(self.offset_to_display(range.end)),
                 bounds.bottom(),
             ),
         ))
@@ -363,20 +909,42 @@ impl EntityInputHandler for TextField {
         let line_point = self.last_bounds?.localize(&point)?;
         let last_layout = self.last_layout.as_ref()?;
 
-        assert_eq!(last_layout.text, self.content);
-        let utf8_index = last_layout.index_for_x(point.x - line_point.x)?;
+        // An empty field lays out its placeholder instead
+        if last_layout.text != self.display_text() {
+            return None;
+        }
+        let utf8_index =
+            self.offset_from_display(last_layout.index_for_x(line_point.x + self.scroll_offset)?);
         Some(self.offset_to_utf16(utf8_index))
     }
 }
 
+/// Everything a shaped line depends on.
+#[derive(Clone, PartialEq)]
+struct LineKey {
+    text: SharedString,
+    font_size: Pixels,
+    runs: Vec<TextRun>,
+}
+
 struct TextElement {
     input: Entity<TextField>,
 }
 
 struct PrepaintState {
     line: Option<ShapedLine>,
+    line_key: Option<LineKey>,
+    /// Where the line is drawn, shifted left by `scroll_offset`.
+    line_bounds: Bounds<Pixels>,
+    scroll_offset: Pixels,
+    line_height: Pixels,
+    /// Where dragged text would be dropped.
+    drop_caret: Option<PaintQuad>,
+    /// Stands in for the line when the text is too small to read.
+    greeked: Option<PaintQuad>,
     cursor: Option<PaintQuad>,
     selection: Option<PaintQuad>,
+    highlights: Vec<PaintQuad>,
 }
 
 impl IntoElement for TextElement {
@@ -401,9 +969,12 @@ impl Element for TextElement {
         window: &mut Window,
         cx: &mut App,
     ) -> (LayoutId, Self::RequestLayoutState) {
+        // The text style carries the zoomed font size, so the line height
+        // follows the zoom along with it
+        let line_height = window.text_style().line_height_in_pixels(window.rem_size());
         let mut style = Style::default();
         style.size.width = relative(1.).into();
-        style.size.height = window.line_height().into();
+        style.size.height = line_height.into();
         (window.request_layout(style, [], cx), ())
     }
 
@@ -419,12 +990,60 @@ impl Element for TextElement {
         let content = input.content.clone();
         let selected_range = input.selected_range.clone();
         let cursor = input.cursor_offset();
+        let alignment = input.alignment;
         let style = window.text_style();
+        let theme = Theme::get(cx);
+        let font_size = style.font_size.to_pixels(window.rem_size());
+        let line_height = style.line_height_in_pixels(window.rem_size());
+
+        if f32::from(font_size) < GREEKING_THRESHOLD {
+            let greeked = (!content.is_empty()).then(|| {
+                let width = (content.graphemes(true).count() as f32
+                    * f32::from(font_size)
+                    * GREEKED_GLYPH_WIDTH)
+                    .min(f32::from(bounds.size.width));
+                let slack = f32::from(bounds.size.width) - width;
+                let left = match alignment {
+                    TextAlignment::Left => 0.0,
+                    TextAlignment::Center => slack / 2.0,
+                    TextAlignment::Right => slack,
+                };
+                let bar_height = bounds.size.height / 2.0;
+                fill(
+                    Bounds::new(
+                        point(
+                            bounds.left() + px(left),
+                            bounds.top() + (bounds.size.height - bar_height) / 2.0,
+                        ),
+                        size(px(width), bar_height),
+                    ),
+                    style.color.opacity(0.4),
+                )
+            });
+            return PrepaintState {
+                line: None,
+                line_key: None,
+                line_bounds: bounds,
+                scroll_offset: px(0.0),
+                line_height,
+                drop_caret: None,
+                greeked,
+                cursor: None,
+                selection: None,
+                highlights: Vec::new(),
+            };
+        }
+        // Caret and underline scale with the text so they stay in proportion
+        // at any zoom, but never get thinner than a pixel
+        let stroke = px((f32::from(font_size) / 16.0).max(1.0));
 
         let (display_text, text_color) = if content.is_empty() {
-            (input.placeholder.clone(), hsla(0., 0., 0., 0.2))
+            (input.placeholder.clone(), theme.placeholder_text)
         } else {
-            (content.clone(), style.color)
+            (input.display_text(), style.color)
+        };
+        let display_range = |range: &Range<usize>| {
+            input.offset_to_display(range.start)..input.offset_to_display(range.end)
         };
 
         let run = TextRun {
@@ -435,7 +1054,7 @@ impl Element for TextElement {
             underline: None,
             strikethrough: None,
         };
-        let runs = if let Some(marked_range) = input.marked_range.as_ref() {
+        let runs = if let Some(marked_range) = input.marked_range.as_ref().map(display_range) {
             vec![
                 TextRun {
                     len: marked_range.start,
@@ -445,7 +1064,7 @@ impl Element for TextElement {
                     len: marked_range.end - marked_range.start,
                     underline: Some(UnderlineStyle {
                         color: Some(run.color),
-                        thickness: px(1.0),
+                        thickness: stroke,
                         wavy: false,
                     }),
                     ..run.clone()
@@ -462,23 +1081,109 @@ impl Element for TextElement {
             vec![run]
         };
 
-        let font_size = style.font_size.to_pixels(window.rem_size());
-        let line = window
-            .text_system()
-            .shape_line(display_text, font_size, &runs)
-            .unwrap();
+        let line_key = LineKey {
+            text: display_text,
+            font_size,
+            runs,
+        };
+        let line = match (&input.last_layout, &input.last_layout_key) {
+            (Some(line), Some(key)) if *key == line_key => line.clone(),
+            _ => window
+                .text_system()
+                .shape_line(line_key.text.clone(), font_size, &line_key.runs)
+                .unwrap(),
+        };
 
-        let cursor_pos = line.x_for_index(cursor);
+        let slack = px((f32::from(bounds.size.width) - f32::from(line.width)).max(0.0));
+        let line_offset = match alignment {
+            TextAlignment::Left => px(0.0),
+            TextAlignment::Center => slack / 2.0,
+            TextAlignment::Right => slack,
+        };
+        let cursor_pos = line.x_for_index(input.offset_to_display(cursor));
+        let caret_style = Settings::get(cx).caret.style;
+        let caret_width = match caret_style {
+            CaretStyle::Bar => stroke * 2.0,
+            // Block and underline carets cover the next character, or a
+            // typical one's width at the end of the line
+            CaretStyle::Block | CaretStyle::Underline if cursor < content.len() => {
+                line.x_for_index(input.offset_to_display(input.next_boundary(cursor))) - cursor_pos
+            }
+            CaretStyle::Block | CaretStyle::Underline => font_size * END_CARET_WIDTH,
+        };
+        let scroll_offset = scroll_to_caret(
+            input.scroll_offset,
+            cursor_pos,
+            caret_width,
+            line.width,
+            bounds.size.width,
+        );
+        let bounds = Bounds::new(
+            point(bounds.left() + line_offset - scroll_offset, bounds.top()),
+            bounds.size,
+        );
+
+        let highlights = input
+            .highlights
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| range.end <= content.len())
+            .map(|(idx, range)| {
+                let range = display_range(range);
+                fill(
+                    Bounds::from_corners(
+                        point(bounds.left() + line.x_for_index(range.start), bounds.top()),
+                        point(bounds.left() + line.x_for_index(range.end), bounds.bottom()),
+                    ),
+                    if input.active_highlight == Some(idx) {
+                        theme.search_match_active
+                    } else {
+                        theme.search_match
+                    },
+                )
+            })
+            .collect();
+
+        let selected_range = display_range(&selected_range);
+        let drop_caret = input.drop_offset.map(|offset| {
+            fill(
+                Bounds::new(
+                    point(
+                        bounds.left() + line.x_for_index(input.offset_to_display(offset)),
+                        bounds.top(),
+                    ),
+                    size(stroke * 2.0, bounds.size.height),
+                ),
+                theme.text_cursor.opacity(0.6),
+            )
+        });
         let (selection, cursor) = if selected_range.is_empty() {
             (
                 None,
-                Some(fill(
-                    Bounds::new(
-                        point(bounds.left() + cursor_pos, bounds.top()),
-                        size(px(2.), bounds.bottom() - bounds.top()),
+                Some(match caret_style {
+                    CaretStyle::Bar => fill(
+                        Bounds::new(
+                            point(bounds.left() + cursor_pos, bounds.top()),
+                            size(caret_width, bounds.size.height),
+                        ),
+                        theme.text_cursor,
                     ),
-                    gpui::blue(),
-                )),
+                    // See-through, so the character under it stays readable
+                    CaretStyle::Block => fill(
+                        Bounds::new(
+                            point(bounds.left() + cursor_pos, bounds.top()),
+                            size(caret_width, bounds.size.height),
+                        ),
+                        theme.text_cursor.opacity(0.4),
+                    ),
+                    CaretStyle::Underline => fill(
+                        Bounds::new(
+                            point(bounds.left() + cursor_pos, bounds.bottom() - stroke * 2.0),
+                            size(caret_width, stroke * 2.0),
+                        ),
+                        theme.text_cursor,
+                    ),
+                }),
             )
         } else {
             (
@@ -493,15 +1198,22 @@ impl Element for TextElement {
                             bounds.bottom(),
                         ),
                     ),
-                    rgba(0x3311ff30),
+                    theme.text_selection,
                 )),
                 None,
             )
         };
         PrepaintState {
             line: Some(line),
+            line_key: Some(line_key),
+            line_bounds: bounds,
+            scroll_offset,
+            line_height,
+            drop_caret,
+            greeked: None,
             cursor,
             selection,
+            highlights,
         }
     }
 
@@ -514,39 +1226,175 @@ impl Element for TextElement {
         window: &mut Window,
         cx: &mut App,
     ) {
-        let focus_handle = self.input.read(cx).focus_handle.clone();
+        let input = self.input.read(cx);
+        let focus_handle = input.focus_handle.clone();
+        let caret_visible = input.caret_visible;
         window.handle_input(
             &focus_handle,
             ElementInputHandler::new(bounds, self.input.clone()),
             cx,
         );
-        if let Some(selection) = prepaint.selection.take() {
-            window.paint_quad(selection)
-        }
-        let line = prepaint.line.take().unwrap();
-        line.paint(bounds.origin, window.line_height(), window, cx)
-            .unwrap();
+        let line = prepaint.line.take();
+        let line_bounds = prepaint.line_bounds;
+        // Scrolled text stays inside the field
+        window.with_content_mask(Some(ContentMask { bounds }), |window| {
+            for highlight in prepaint.highlights.drain(..) {
+                window.paint_quad(highlight);
+            }
+            if let Some(selection) = prepaint.selection.take() {
+                window.paint_quad(selection)
+            }
+            if let Some(greeked) = prepaint.greeked.take() {
+                window.paint_quad(greeked);
+            }
+            if let Some(line) = line.as_ref() {
+                line.paint(line_bounds.origin, prepaint.line_height, window, cx)
+                    .unwrap();
+            }
 
-        if focus_handle.is_focused(window) {
-            if let Some(cursor) = prepaint.cursor.take() {
-                window.paint_quad(cursor);
+            if let Some(drop_caret) = prepaint.drop_caret.take() {
+                window.paint_quad(drop_caret);
             }
-        }
+            if focus_handle.is_focused(window) && caret_visible {
+                if let Some(cursor) = prepaint.cursor.take() {
+                    window.paint_quad(cursor);
+                }
+            }
+        });
 
+        let scroll_offset = prepaint.scroll_offset;
         self.input.update(cx, |input, _cx| {
-            input.last_layout = Some(line);
-            input.last_bounds = Some(bounds);
+            // Greeked text has no layout, so mouse and IME lookups fall back
+            // to the start of the field rather than using a stale line
+            input.last_layout = line;
+            input.last_layout_key = prepaint.line_key.take();
+            input.last_bounds = Some(Bounds::new(
+                point(line_bounds.left() + scroll_offset, line_bounds.top()),
+                line_bounds.size,
+            ));
+            input.scroll_offset = scroll_offset;
         });
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn read_primary_selection(cx: &App) -> Option<String> {
+    cx.read_from_primary().and_then(|item| item.text())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn read_primary_selection(_: &App) -> Option<String> {
+    None
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn write_primary_selection(text: String, cx: &App) {
+    cx.write_to_primary(ClipboardItem::new_string(text));
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn write_primary_selection(_: String, _: &App) {}
+
+/// Whether dropping dragged text copies it instead of moving it.
+fn is_copy_drag(modifiers: &Modifiers) -> bool {
+    if cfg!(target_os = "macos") {
+        modifiers.alt
+    } else {
+        modifiers.control
+    }
+}
+
+/// What replacing `range` of `content` with `new_text` inserts after the
+/// `filters`, in order, and the `max_length` cap, or `None` if rejected.
+fn filter_input(
+    content: &str,
+    range: &Range<usize>,
+    new_text: &str,
+    max_length: Option<usize>,
+    filters: &[InputFilter],
+) -> Option<String> {
+    let mut text = new_text.to_string();
+    for filter in filters {
+        match filter {
+            InputFilter::Numeric => text.retain(|ch| ch.is_ascii_digit()),
+            InputFilter::Custom(filter) => text = filter(&text)?,
+            InputFilter::Regex(_) => {}
+        }
+    }
+    if let Some(max_length) = max_length {
+        let kept = content[..range.start].graphemes(true).count()
+            + content[range.end..].graphemes(true).count();
+        text = text
+            .graphemes(true)
+            .take(max_length.saturating_sub(kept))
+            .collect();
+    }
+    // Typing over a selection shouldn't just delete it
+    if text.is_empty() && !new_text.is_empty() {
+        return None;
+    }
+
+    let edited = content[..range.start].to_owned() + &text + &content[range.end..];
+    filters
+        .iter()
+        .all(|filter| match filter {
+            InputFilter::Regex(regex) => regex.is_match(&edited),
+            _ => true,
+        })
+        .then_some(text)
+}
+
+/// Where a UTF-8 offset into `content` falls once it's drawn as bullets.
+fn masked_offset(content: &str, offset: usize) -> usize {
+    content[..offset].graphemes(true).count() * MASK_CHAR.len_utf8()
+}
+
+/// The offset into `content` drawn at a UTF-8 offset into its bullets.
+fn unmasked_offset(content: &str, offset: usize) -> usize {
+    content
+        .grapheme_indices(true)
+        .nth(offset / MASK_CHAR.len_utf8())
+        .map_or(content.len(), |(idx, _)| idx)
+}
+
+/// The smallest change to `scroll_offset` that brings the caret at
+/// `caret_x` into a field `visible_width` wide, without scrolling past
+/// either end of a line `line_width` wide.
+fn scroll_to_caret(
+    scroll_offset: Pixels,
+    caret_x: Pixels,
+    caret_width: Pixels,
+    line_width: Pixels,
+    visible_width: Pixels,
+) -> Pixels {
+    let visible_width = f32::from(visible_width);
+    let caret_x = f32::from(caret_x);
+    let caret_end = caret_x + f32::from(caret_width);
+    let mut scroll_offset = f32::from(scroll_offset);
+    if caret_x < scroll_offset {
+        scroll_offset = caret_x;
+    } else if caret_end > scroll_offset + visible_width {
+        scroll_offset = caret_end - visible_width;
+    }
+    let max_scroll = (f32::from(line_width) + f32::from(caret_width) - visible_width).max(0.0);
+    px(scroll_offset.clamp(0.0, max_scroll))
+}
+
 impl Render for TextField {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let interactive = self.interactive;
         div()
             .flex()
             .key_context("TextField")
-            .track_focus(&self.focus_handle(cx))
-            .cursor(CursorStyle::IBeam)
+            .when(interactive, |div| {
+                div.track_focus(&self.focus_handle(cx))
+                    .cursor(CursorStyle::IBeam)
+                    .on_mouse_down(MouseButton::Left, cx.listener(Self::on_mouse_down))
+                    .on_mouse_down(MouseButton::Middle, cx.listener(Self::on_middle_mouse_down))
+                    .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
+                    .on_mouse_up_out(MouseButton::Left, cx.listener(Self::on_mouse_up_out))
+                    .on_mouse_move(cx.listener(Self::on_mouse_move))
+            })
             .on_action(cx.listener(Self::backspace))
             .on_action(cx.listener(Self::delete))
             .on_action(cx.listener(Self::left))
@@ -559,18 +1407,139 @@ impl Render for TextField {
             .on_action(cx.listener(Self::paste))
             .on_action(cx.listener(Self::cut))
             .on_action(cx.listener(Self::copy))
-            .on_mouse_down(MouseButton::Left, cx.listener(Self::on_mouse_down))
-            .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
-            .on_mouse_up_out(MouseButton::Left, cx.listener(Self::on_mouse_up))
-            .on_mouse_move(cx.listener(Self::on_mouse_move))
+            .on_action(cx.listener(Self::submit))
+            .on_action(cx.listener(Self::show_character_palette))
             .child(TextElement {
                 input: cx.entity().clone(),
             })
+            .when(self.masked && self.reveal_toggle, |div| {
+                div.child(
+                    div()
+                        .id("reveal")
+                        .px_1()
+                        .cursor(CursorStyle::PointingHand)
+                        .child(if self.revealed { "◎" } else { "◉" })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|this, _, _, cx| {
+                                // Don't move the caret to where the button is
+                                cx.stop_propagation();
+                                this.set_revealed(true, cx);
+                            }),
+                        )
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this, _, _, cx| this.set_revealed(false, cx)),
+                        )
+                        .on_mouse_up_out(
+                            MouseButton::Left,
+                            cx.listener(|this, _, _, cx| this.set_revealed(false, cx)),
+                        ),
+                )
+            })
     }
 }
 
+impl EventEmitter<TextFieldEvent> for TextField {}
+
 impl Focusable for TextField {
     fn focus_handle(&self, _: &App) -> FocusHandle {
         self.focus_handle.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_filter_keeps_digits() {
+        let filters = [InputFilter::Numeric];
+        assert_eq!(
+            filter_input("", &(0..0), "a1b2", None, &filters),
+            Some("12".into())
+        );
+        assert_eq!(filter_input("", &(0..0), "abc", None, &filters), None);
+        // Deleting is never filtered away
+        assert_eq!(
+            filter_input("12", &(0..2), "", None, &filters),
+            Some("".into())
+        );
+    }
+
+    #[test]
+    fn filters_run_in_order() {
+        let zeroes = || InputFilter::Custom(Rc::new(|text: &str| Some(text.replace('o', "0"))));
+        let filters = [zeroes(), InputFilter::Numeric];
+        assert_eq!(
+            filter_input("", &(0..0), "foo", None, &filters),
+            Some("00".into())
+        );
+        let filters = [InputFilter::Numeric, zeroes()];
+        assert_eq!(filter_input("", &(0..0), "foo", None, &filters), None);
+
+        let reject = InputFilter::Custom(Rc::new(|_: &str| None));
+        assert_eq!(filter_input("", &(0..0), "foo", None, &[reject]), None);
+    }
+
+    #[test]
+    fn max_length_counts_graphemes() {
+        assert_eq!(
+            filter_input("ab", &(2..2), "cde", Some(4), &[]),
+            Some("cd".into())
+        );
+        assert_eq!(
+            filter_input("", &(0..0), "e\u{301}x", Some(1), &[]),
+            Some("e\u{301}".into())
+        );
+        // The replaced text makes room
+        assert_eq!(
+            filter_input("abc", &(0..3), "xyz", Some(3), &[]),
+            Some("xyz".into())
+        );
+        assert_eq!(filter_input("abc", &(3..3), "d", Some(3), &[]), None);
+    }
+
+    #[test]
+    fn regex_filter_checks_the_edited_text() {
+        let filters = [InputFilter::Regex(Regex::new(r"^\d{0,3}$").unwrap())];
+        assert_eq!(
+            filter_input("12", &(2..2), "3", None, &filters),
+            Some("3".into())
+        );
+        assert_eq!(filter_input("12", &(2..2), "34", None, &filters), None);
+        assert_eq!(filter_input("12", &(0..0), "x", None, &filters), None);
+    }
+
+    #[test]
+    fn masked_offsets_round_trip() {
+        let content = "ae\u{301}€";
+        let pairs = [(0, 0), (1, 3), (4, 6), (7, 9)];
+        for (offset, display) in pairs {
+            assert_eq!(masked_offset(content, offset), display);
+            assert_eq!(unmasked_offset(content, display), offset);
+        }
+        // Inside a bullet maps back to the grapheme it stands for
+        assert_eq!(unmasked_offset(content, 4), 1);
+    }
+
+    #[test]
+    fn scroll_brings_caret_into_view() {
+        let scroll = |offset, caret_x| {
+            scroll_to_caret(px(offset), px(caret_x), px(2.0), px(300.0), px(100.0))
+        };
+        assert_eq!(scroll(0.0, 50.0), px(0.0));
+        assert_eq!(scroll(0.0, 150.0), px(52.0));
+        assert_eq!(scroll(100.0, 40.0), px(40.0));
+        // Never past the end of the line
+        assert_eq!(scroll(250.0, 280.0), px(202.0));
+    }
+
+    #[test]
+    fn short_lines_never_scroll() {
+        assert_eq!(
+            scroll_to_caret(px(30.0), px(40.0), px(2.0), px(50.0), px(100.0)),
+            px(0.0)
+        );
+    }
+}
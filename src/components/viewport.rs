@@ -1,106 +1,1908 @@
-use super::draggable_textbox::DraggableTextBox;
-use crate::common::{DragState, Viewport};
+use super::draggable_textbox::{image_kind, DraggableTextBox, NodeImage};
+use super::search_bar::SearchBar;
+use super::style_inspector::StyleInspector;
+use super::text_field::{TextField, TextFieldEvent};
+use crate::common::{
+    board::{BoardSnapshot, NodeData},
+    frame::{self, FrameData, FRAME_PADDING, FRAME_TITLE_HEIGHT},
+    history::History,
+    images,
+    search::{SearchOptions, SearchQuery},
+    settings::{LevelOfDetail, Settings},
+    shape::NodeShape,
+    style::{NodeStyle, StyleEdit},
+    theme::{Theme, Themes},
+    DragState, Viewport,
+};
+use gpui::prelude::FluentBuilder;
 use gpui::*;
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+actions!(
+    viewport,
+    [
+        PanLeft,
+        PanRight,
+        PanUp,
+        PanDown,
+        FocusNextBox,
+        FocusPreviousBox,
+        FocusBoxLeft,
+        FocusBoxRight,
+        FocusBoxUp,
+        FocusBoxDown,
+        EditFocusedBox,
+        StopEditing,
+        ClearSelection,
+        NudgeLeftByGrid,
+        NudgeRightByGrid,
+        NudgeUpByGrid,
+        NudgeDownByGrid,
+        Undo,
+        Redo,
+        CopyBoxes,
+        CutBoxes,
+        PasteBoxes,
+        DuplicateBoxes,
+        InsertImage,
+        EmbedImages,
+        SaveBoard,
+        OpenBoard,
+        SetShapeRectangle,
+        SetShapeRounded,
+        SetShapeEllipse,
+        SetShapeDiamond,
+        SetShapeStickyNote,
+        CopyStyle,
+        PasteStyle,
+        BringForward,
+        SendBackward,
+        BringToFront,
+        SendToBack,
+        ToggleCollisions,
+        FrameSelection,
+        ToggleFrameCollapsed,
+        ToggleLocked,
+        ToggleHidden,
+        ToggleShowHidden,
+        OpenSearch,
+        CloseSearch,
+        SearchNext,
+        SearchPrevious,
+        ToggleSearchCaseSensitive,
+        ToggleSearchRegex,
+        ToggleReplace,
+        ReplaceOne,
+        ReplaceAll,
+    ]
+);
+
+/// Distance in screen pixels the canvas moves per arrow key press.
+const PAN_STEP: f32 = 50.0;
+/// Distance in world units selected boxes move per shift+arrow key press.
+const GRID_STEP: f32 = 10.0;
+const HISTORY_LIMIT: usize = 100;
+/// Offset in world units applied to duplicated boxes.
+const DUPLICATE_OFFSET: f32 = 20.0;
+/// Size in world units of boxes created from pasted text.
+const DEFAULT_BOX_SIZE: (f32, f32) = (200.0, 100.0);
+/// Vertical gap in world units between boxes created from pasted paragraphs.
+const PASTE_COLUMN_GAP: f32 = 20.0;
+/// How long the viewport takes to glide to a search match.
+const VIEWPORT_ANIMATION_DURATION: Duration = Duration::from_millis(300);
+/// Size in world units of a frame added with nothing selected.
+const DEFAULT_FRAME_SIZE: (f32, f32) = (400.0, 300.0);
+/// Smallest width or height in world units a box can be resized to.
+const MIN_BOX_SIDE: f32 = 20.0;
+
+#[derive(Clone, Debug, PartialEq)]
+struct SearchMatch {
+    box_idx: usize,
+    range: Range<usize>,
+}
+
+/// Results for the query in the search bar while it's open.
+#[derive(Clone, Debug, Default)]
+struct SearchState {
+    query: String,
+    compiled: Option<SearchQuery>,
+    matches: Vec<SearchMatch>,
+    current: Option<usize>,
+    error: Option<String>,
+    /// Whether the replacement row and its preview are shown.
+    replacing: bool,
+}
+
+/// A glide of the viewport's center from one point to another.
+#[derive(Clone, Copy)]
+struct ViewportAnimation {
+    from: Point<Pixels>,
+    to: Point<Pixels>,
+    start: Instant,
+}
 
 #[derive(Clone)]
 pub struct ViewportApp {
     textboxes: Vec<Entity<DraggableTextBox>>,
     viewport: Viewport,
     is_dragging: Option<usize>,
+    /// Boxes inside the frame being dragged, which move along with it.
+    drag_children: Vec<usize>,
     drag_offset: Option<Point<Pixels>>,
     is_panning: bool,
     last_mouse_pos: Option<Point<Pixels>>,
     focus_handle: FocusHandle,
     last_move_direction: Option<Point<Pixels>>,
+    focused_box: Option<usize>,
+    selection: Vec<usize>,
+    history: History<BoardSnapshot>,
+    drag_start_snapshot: Option<BoardSnapshot>,
+    is_resizing: Option<usize>,
+    /// Width to height ratio kept while resizing, for image boxes.
+    resize_aspect: Option<f32>,
+    board_path: Option<PathBuf>,
+    copied_style: Option<NodeStyle>,
+    /// Whether moving or growing a box pushes the boxes it runs into.
+    collisions_enabled: bool,
+    /// Whether hidden boxes are drawn, faded, so they can be found again.
+    show_hidden: bool,
+    search_field: Entity<TextField>,
+    replace_field: Entity<TextField>,
+    search: Option<SearchState>,
+    search_options: SearchOptions,
+    viewport_animation: Option<ViewportAnimation>,
+    /// Frame membership as last worked out, with the box bounds it was
+    /// worked out from, reused until a box moves, resizes, comes or goes.
+    parents_cache: RefCell<(Vec<(Bounds<Pixels>, bool)>, Rc<[Option<usize>]>)>,
 }
 
-impl ViewportApp {
-    pub fn new(cx: &mut Context<Self>) -> Self {
-        let textbox1 = cx.new(|cx| {
-            DraggableTextBox::new(
-                "Hello World".into(),
-                point(px(100.0), px(100.0)),
-                size(px(200.0), px(100.0)),
-                0,
-                cx,
-            )
+impl ViewportApp {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        cx.observe_window_appearance(window, |_, window, cx| {
+            Themes::activate(window.appearance().into(), cx);
+            cx.notify();
+        })
+        .detach();
+
+        let search_field = cx.new(|cx| TextField::new("".into(), cx).placeholder("Search"));
+        cx.subscribe(&search_field, |this, _, event, cx| {
+            if *event == TextFieldEvent::Changed {
+                this.refresh_search(cx);
+            }
+        })
+        .detach();
+
+        let replace_field = cx.new(|cx| TextField::new("".into(), cx).placeholder("Replace"));
+        // Redraw the preview as the replacement is typed
+        cx.subscribe(&replace_field, |_, _, event, cx| {
+            if *event == TextFieldEvent::Changed {
+                cx.notify();
+            }
+        })
+        .detach();
+
+        let textbox1 = cx.new(|cx| {
+            DraggableTextBox::new(
+                "Hello World".into(),
+                point(px(100.0), px(100.0)),
+                size(px(200.0), px(100.0)),
+                0,
+                cx,
+            )
+        });
+
+        let textbox2 = cx.new(|cx| {
+            DraggableTextBox::new(
+                "Second Box".into(),
+                point(px(400.0), px(300.0)),
+                size(px(200.0), px(100.0)),
+                1,
+                cx,
+            )
+        });
+
+        Self::watch_textbox(&textbox1, cx);
+        Self::watch_textbox(&textbox2, cx);
+
+        Self {
+            textboxes: vec![textbox1, textbox2],
+            viewport: Viewport::new(),
+            is_dragging: None,
+            drag_children: Vec::new(),
+            drag_offset: None,
+            is_panning: false,
+            last_mouse_pos: None,
+            focus_handle: cx.focus_handle(),
+            last_move_direction: None,
+            focused_box: None,
+            selection: Vec::new(),
+            history: History::new(HISTORY_LIMIT),
+            drag_start_snapshot: None,
+            is_resizing: None,
+            resize_aspect: None,
+            board_path: None,
+            copied_style: None,
+            collisions_enabled: true,
+            show_hidden: false,
+            search_field,
+            replace_field,
+            search: None,
+            search_options: SearchOptions::default(),
+            viewport_animation: None,
+            parents_cache: RefCell::new((Vec::new(), Rc::from([]))),
+        }
+    }
+
+    /// Moves a box, along with the `children` it carries when it's a frame.
+    fn update_textbox_position(
+        &mut self,
+        index: usize,
+        new_position: Point<Pixels>,
+        children: &[usize],
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(textbox) = self.textboxes.get(index) {
+            let old_position = textbox.read(cx).position;
+            let move_delta = point(
+                new_position.x - old_position.x,
+                new_position.y - old_position.y,
+            );
+            self.last_move_direction = Some(move_delta);
+            for &idx in std::iter::once(&index).chain(children) {
+                self.translate_box(idx, move_delta, cx);
+            }
+
+            self.handle_collisions(&[index], cx);
+        }
+    }
+
+    fn translate_box(&self, index: usize, delta: Point<Pixels>, cx: &mut Context<Self>) {
+        if let Some(textbox) = self.textboxes.get(index) {
+            textbox.update(cx, |textbox, cx| {
+                textbox.position =
+                    point(textbox.position.x + delta.x, textbox.position.y + delta.y);
+                cx.notify();
+            });
+        }
+    }
+
+    /// Innermost frame containing each box, by index.
+    fn parents(&self, cx: &App) -> Rc<[Option<usize>]> {
+        let boxes: Vec<_> = self
+            .textboxes
+            .iter()
+            .map(|textbox| {
+                let textbox = textbox.read(cx);
+                (textbox.extent(), textbox.is_frame())
+            })
+            .collect();
+        let mut cache = self.parents_cache.borrow_mut();
+        if cache.0 != boxes {
+            let parents = frame::parents(&boxes).into();
+            *cache = (boxes, parents);
+        }
+        cache.1.clone()
+    }
+
+    /// Whether each box is out of sight: tucked away in a collapsed frame,
+    /// or hidden, itself or through its frame, while hidden boxes aren't shown.
+    fn concealed(&self, parents: &[Option<usize>], cx: &App) -> Vec<bool> {
+        let is_hidden = |idx: usize| !self.show_hidden && self.textboxes[idx].read(cx).hidden;
+        (0..self.textboxes.len())
+            .map(|idx| {
+                is_hidden(idx)
+                    || frame::any_ancestor(parents, idx, |frame| {
+                        is_hidden(frame)
+                            || matches!(
+                                self.textboxes[frame].read(cx).frame,
+                                Some(FrameData { collapsed: true })
+                            )
+                    })
+            })
+            .collect()
+    }
+
+    fn is_locked(&self, idx: usize, cx: &App) -> bool {
+        self.textboxes
+            .get(idx)
+            .is_some_and(|textbox| textbox.read(cx).locked)
+    }
+
+    /// Boxes inside `idx` that move along with it; locked ones stay put.
+    fn carried_children(&self, parents: &[Option<usize>], idx: usize, cx: &App) -> Vec<usize> {
+        frame::descendants(parents, idx)
+            .into_iter()
+            .filter(|&child| !self.is_locked(child, cx))
+            .collect()
+    }
+
+    /// Indices in the order boxes are painted: frames first so the boxes
+    /// they hold stay on top, then everything else, each in board order.
+    fn paint_order(&self, cx: &App) -> Vec<usize> {
+        let (mut frames, boxes): (Vec<usize>, Vec<usize>) =
+            (0..self.textboxes.len()).partition(|&idx| self.textboxes[idx].read(cx).is_frame());
+        frames.extend(boxes);
+        frames
+    }
+
+    /// The selection plus everything inside selected frames, in board order.
+    fn selection_with_contents(&self, cx: &App) -> Vec<usize> {
+        let parents = self.parents(cx);
+        let mut indices = self.selection.clone();
+        for &idx in &self.selection {
+            indices.extend(frame::descendants(&parents, idx));
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Resizes a box so its bottom-right corner follows `corner`, keeping the
+    /// aspect ratio when one is locked, and pushes away boxes it grows into.
+    fn resize_textbox(&mut self, index: usize, corner: Point<Pixels>, cx: &mut Context<Self>) {
+        let Some(textbox) = self.textboxes.get(index) else {
+            return;
+        };
+        let position = textbox.read(cx).position;
+        let mut width = f32::from(corner.x - position.x).max(MIN_BOX_SIDE);
+        let mut height = f32::from(corner.y - position.y).max(MIN_BOX_SIDE);
+        if let Some(aspect) = self.resize_aspect {
+            if width / height > aspect {
+                width = height * aspect;
+            } else {
+                height = width / aspect;
+            }
+        }
+
+        textbox.update(cx, |textbox, cx| {
+            let old_size = textbox.size;
+            textbox.size = size(px(width), px(height));
+            cx.notify();
+
+            // Only growth pushes neighbours; shrinking leaves them in place
+            self.last_move_direction = Some(point(
+                px((width - f32::from(old_size.width)).max(0.0)),
+                px((height - f32::from(old_size.height)).max(0.0)),
+            ));
+        });
+
+        self.handle_collisions(&[index], cx);
+    }
+
+    /// Pushes aside whatever the `moving` boxes, which moved together, now
+    /// overlap.
+    fn handle_collisions(&mut self, moving: &[usize], cx: &mut Context<Self>) {
+        if !self.collisions_enabled {
+            return;
+        }
+        let parents = self.parents(cx);
+        // A frame's contents already moved with it, boxes out of sight aren't
+        // there to run into, and locked boxes never get pushed
+        let mut moved = moving.to_vec();
+        for &idx in moving {
+            moved.extend(frame::descendants(&parents, idx));
+        }
+        let concealed = self.concealed(&parents, cx);
+        moved.extend(
+            (0..self.textboxes.len()).filter(|&idx| concealed[idx] || self.is_locked(idx, cx)),
+        );
+        for &idx in moving {
+            self.push_overlapping(idx, &parents, &mut moved, cx);
+        }
+    }
+
+    /// Pushes boxes overlapping `moving_idx` along the last move, then the
+    /// boxes those run into. Each box moves at most once per change, so boxes
+    /// that already overlapped can't push each other back and forth forever.
+    /// Only boxes in the same frame collide, and a pushed frame takes its
+    /// contents along, so each frame's contents act as one unit.
+    fn push_overlapping(
+        &mut self,
+        moving_idx: usize,
+        parents: &[Option<usize>],
+        moved: &mut Vec<usize>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(move_delta) = self.last_move_direction else {
+            return;
+        };
+        if move_delta == point(px(0.0), px(0.0)) {
+            return;
+        }
+
+        let mut boxes_to_move = Vec::new();
+        if let Some(moving_box) = self.textboxes.get(moving_idx) {
+            let moving_box_data = moving_box.read(cx);
+
+            for (idx, other_box) in self.textboxes.iter().enumerate() {
+                if !moved.contains(&idx) && parents[idx] == parents[moving_idx] {
+                    let other_box_data = other_box.read(cx);
+                    if moving_box_data.overlaps(other_box_data) {
+                        boxes_to_move.push(idx);
+                    }
+                }
+            }
+        }
+        moved.extend(&boxes_to_move);
+
+        for idx in boxes_to_move {
+            let children = self.carried_children(parents, idx, cx);
+            moved.extend(&children);
+            for &idx in std::iter::once(&idx).chain(&children) {
+                self.translate_box(idx, move_delta, cx);
+            }
+            self.push_overlapping(idx, parents, moved, cx);
+        }
+    }
+
+    /// Topmost box whose shape contains the given screen position.
+    fn box_at(&self, screen_position: Point<Pixels>, cx: &App) -> Option<usize> {
+        let world_position = self.viewport.inverse_transform_point(screen_position);
+        let hidden = self.concealed(&self.parents(cx), cx);
+        self.paint_order(cx)
+            .into_iter()
+            .rev()
+            .find(|&idx| !hidden[idx] && self.textboxes[idx].read(cx).contains(world_position))
+    }
+
+    /// Topmost box whose shape or resize handle is under the given screen
+    /// position.
+    fn box_or_handle_at(&self, screen_position: Point<Pixels>, cx: &App) -> Option<usize> {
+        let world_position = self.viewport.inverse_transform_point(screen_position);
+        let hidden = self.concealed(&self.parents(cx), cx);
+        self.paint_order(cx).into_iter().rev().find(|&idx| {
+            let textbox = self.textboxes[idx].read(cx);
+            !hidden[idx]
+                && (textbox.contains(world_position)
+                    || textbox
+                        .resize_handle(&self.viewport)
+                        .contains(&screen_position))
+        })
+    }
+
+    fn start_box_drag(&mut self, idx: usize, event: &MouseDownEvent, cx: &mut Context<Self>) {
+        let Some(textbox) = self.textboxes.get(idx) else {
+            return;
+        };
+        let screen_position = self.viewport.transform_point(textbox.read(cx).position);
+        // Locked boxes can still be selected and their text edited, just
+        // not moved
+        if !textbox.read(cx).locked {
+            self.is_dragging = Some(idx);
+            self.drag_children = self.carried_children(&self.parents(cx), idx, cx);
+        }
+        self.focused_box = Some(idx);
+        if event.modifiers.shift {
+            if let Some(pos) = self.selection.iter().position(|&i| i == idx) {
+                self.selection.remove(pos);
+            } else {
+                self.selection.push(idx);
+            }
+        } else if !self.selection.contains(&idx) {
+            self.selection = vec![idx];
+        }
+        self.drag_start_snapshot = Some(self.snapshot(cx));
+        self.drag_offset = Some(point(
+            event.position.x - screen_position.x,
+            event.position.y - screen_position.y,
+        ));
+        cx.notify();
+    }
+
+    fn set_selection_shape(&mut self, shape: NodeShape, cx: &mut Context<Self>) {
+        if self.selection.is_empty() {
+            return;
+        }
+        self.history.push(self.snapshot(cx));
+        for &idx in &self.selection {
+            if let Some(textbox) = self.textboxes.get(idx) {
+                textbox.update(cx, |textbox, cx| {
+                    // Frames are always rectangular regions
+                    if !textbox.is_frame() {
+                        textbox.shape = shape;
+                        cx.notify();
+                    }
+                });
+            }
+        }
+        cx.notify();
+    }
+
+    fn set_shape_rectangle(
+        &mut self,
+        _: &SetShapeRectangle,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_selection_shape(NodeShape::Rectangle, cx);
+    }
+
+    fn set_shape_rounded(&mut self, _: &SetShapeRounded, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_selection_shape(NodeShape::Rounded, cx);
+    }
+
+    fn set_shape_ellipse(&mut self, _: &SetShapeEllipse, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_selection_shape(NodeShape::Ellipse, cx);
+    }
+
+    fn set_shape_diamond(&mut self, _: &SetShapeDiamond, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_selection_shape(NodeShape::Diamond, cx);
+    }
+
+    fn set_shape_sticky_note(
+        &mut self,
+        _: &SetShapeStickyNote,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_selection_shape(NodeShape::StickyNote, cx);
+    }
+
+    /// Rearranges boxes so `order[i]` is the index of the box to paint `i`th,
+    /// as one undo step.
+    fn reorder_boxes(&mut self, order: Vec<usize>, cx: &mut Context<Self>) {
+        if order.iter().copied().eq(0..order.len()) {
+            return;
+        }
+        self.history.push(self.snapshot(cx));
+        self.apply_order(&order, cx);
+    }
+
+    /// Rearranges boxes like `reorder_boxes`, keeping selection, focus and
+    /// search matches on the same boxes.
+    fn apply_order(&mut self, order: &[usize], cx: &mut Context<Self>) {
+        let new_index = |old: usize| order.iter().position(|&idx| idx == old);
+        self.selection = self
+            .selection
+            .iter()
+            .filter_map(|&idx| new_index(idx))
+            .collect();
+        self.focused_box = self.focused_box.and_then(new_index);
+        if let Some(search) = &mut self.search {
+            for found in &mut search.matches {
+                found.box_idx = new_index(found.box_idx).unwrap_or(found.box_idx);
+            }
+        }
+        self.textboxes = order
+            .iter()
+            .map(|&idx| self.textboxes[idx].clone())
+            .collect();
+        self.reindex_boxes(cx);
+        cx.notify();
+    }
+
+    /// Moves each selected box one step up or down past its unselected
+    /// neighbour.
+    fn step_selection(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let order = Self::stepped_order(self.textboxes.len(), &self.selection, forward);
+        self.reorder_boxes(order, cx);
+    }
+
+    /// Paint order of `len` boxes after each `selected` one steps past its
+    /// unselected neighbour.
+    fn stepped_order(len: usize, selected: &[usize], forward: bool) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..len).collect();
+        let positions: Vec<usize> = if forward {
+            (0..len.saturating_sub(1)).rev().collect()
+        } else {
+            (1..len).collect()
+        };
+        for pos in positions {
+            let neighbour = if forward { pos + 1 } else { pos - 1 };
+            if selected.contains(&order[pos]) && !selected.contains(&order[neighbour]) {
+                order.swap(pos, neighbour);
+            }
+        }
+        order
+    }
+
+    /// Moves the selected boxes above or below every other box, keeping
+    /// their order among themselves.
+    fn move_selection_to_end(&mut self, front: bool, cx: &mut Context<Self>) {
+        let order = Self::order_with_ends(self.textboxes.len(), &self.selection, front);
+        self.reorder_boxes(order, cx);
+    }
+
+    /// Paint order of `len` boxes with the `selected` ones moved to the
+    /// front or back.
+    fn order_with_ends(len: usize, selected: &[usize], front: bool) -> Vec<usize> {
+        let (selected, rest): (Vec<usize>, Vec<usize>) =
+            (0..len).partition(|idx| selected.contains(idx));
+        if front {
+            rest.into_iter().chain(selected).collect()
+        } else {
+            selected.into_iter().chain(rest).collect()
+        }
+    }
+
+    fn bring_forward(&mut self, _: &BringForward, _: &mut Window, cx: &mut Context<Self>) {
+        self.step_selection(true, cx);
+    }
+
+    fn send_backward(&mut self, _: &SendBackward, _: &mut Window, cx: &mut Context<Self>) {
+        self.step_selection(false, cx);
+    }
+
+    fn bring_to_front(&mut self, _: &BringToFront, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selection_to_end(true, cx);
+    }
+
+    fn send_to_back(&mut self, _: &SendToBack, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selection_to_end(false, cx);
+    }
+
+    fn toggle_collisions(&mut self, _: &ToggleCollisions, _: &mut Window, cx: &mut Context<Self>) {
+        self.collisions_enabled = !self.collisions_enabled;
+        cx.notify();
+    }
+
+    /// Wraps the selection in a new frame, or adds an empty frame in the
+    /// middle of the window when nothing is selected.
+    fn frame_selection(&mut self, _: &FrameSelection, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let bounds = self
+            .selection
+            .iter()
+            .filter_map(|&idx| self.textboxes.get(idx))
+            .map(|textbox| textbox.read(cx).extent())
+            .reduce(|a, b| a.union(&b))
+            .map(|bounds| {
+                Bounds::from_corners(
+                    point(
+                        bounds.left() - px(FRAME_PADDING),
+                        bounds.top() - px(FRAME_PADDING + FRAME_TITLE_HEIGHT),
+                    ),
+                    point(
+                        bounds.right() + px(FRAME_PADDING),
+                        bounds.bottom() + px(FRAME_PADDING),
+                    ),
+                )
+            })
+            .unwrap_or_else(|| {
+                let window_size = window.viewport_size();
+                let center = self.viewport.inverse_transform_point(point(
+                    window_size.width / 2.0,
+                    window_size.height / 2.0,
+                ));
+                let (width, height) = DEFAULT_FRAME_SIZE;
+                Bounds::new(
+                    point(center.x - px(width / 2.0), center.y - px(height / 2.0)),
+                    size(px(width), px(height)),
+                )
+            });
+        let node = NodeData {
+            text: "Frame".into(),
+            x: f32::from(bounds.left()),
+            y: f32::from(bounds.top()),
+            width: f32::from(bounds.size.width),
+            height: f32::from(bounds.size.height),
+            frame: Some(FrameData::default()),
+            ..Default::default()
+        };
+
+        self.history.push(self.snapshot(cx));
+        let frame = self.insert_nodes(&[node], cx)[0];
+        // New frames go to the back so they never cover what they hold
+        let order = Self::order_with_ends(self.textboxes.len(), &[frame], false);
+        self.apply_order(&order, cx);
+        self.selection = vec![0];
+        self.focused_box = Some(0);
+        cx.notify();
+    }
+
+    fn toggle_frames_collapsed(&mut self, indices: &[usize], cx: &mut Context<Self>) {
+        let frames: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|&idx| {
+                self.textboxes
+                    .get(idx)
+                    .is_some_and(|textbox| textbox.read(cx).is_frame())
+            })
+            .collect();
+        if frames.is_empty() {
+            return;
+        }
+        self.history.push(self.snapshot(cx));
+        for idx in frames {
+            self.textboxes[idx].update(cx, |textbox, cx| {
+                if let Some(frame) = textbox.frame.as_mut() {
+                    frame.collapsed = !frame.collapsed;
+                }
+                cx.notify();
+            });
+        }
+        // Boxes that just disappeared into a frame can't stay selected
+        let hidden = self.concealed(&self.parents(cx), cx);
+        self.selection.retain(|&idx| !hidden[idx]);
+        self.focused_box = self.focused_box.filter(|&idx| !hidden[idx]);
+        cx.notify();
+    }
+
+    /// Flips a flag on every selected box, or sets it on all of them when
+    /// they disagree.
+    fn toggle_selection_flag(
+        &mut self,
+        flag: fn(&mut DraggableTextBox) -> &mut bool,
+        cx: &mut Context<Self>,
+    ) {
+        if self.selection.is_empty() {
+            return;
+        }
+        let all_set = self.selection.iter().all(|&idx| {
+            self.textboxes
+                .get(idx)
+                .is_some_and(|textbox| textbox.update(cx, |textbox, _| *flag(textbox)))
+        });
+        self.history.push(self.snapshot(cx));
+        for &idx in &self.selection {
+            if let Some(textbox) = self.textboxes.get(idx) {
+                textbox.update(cx, |textbox, cx| {
+                    *flag(textbox) = !all_set;
+                    cx.notify();
+                });
+            }
+        }
+        cx.notify();
+    }
+
+    fn toggle_locked(&mut self, _: &ToggleLocked, _: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_selection_flag(|textbox| &mut textbox.locked, cx);
+    }
+
+    fn toggle_hidden(&mut self, _: &ToggleHidden, _: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_selection_flag(|textbox| &mut textbox.hidden, cx);
+        // Boxes that just vanished can't stay selected
+        let concealed = self.concealed(&self.parents(cx), cx);
+        self.selection.retain(|&idx| !concealed[idx]);
+        self.focused_box = self.focused_box.filter(|&idx| !concealed[idx]);
+    }
+
+    fn toggle_show_hidden(&mut self, _: &ToggleShowHidden, _: &mut Window, cx: &mut Context<Self>) {
+        self.show_hidden = !self.show_hidden;
+        let concealed = self.concealed(&self.parents(cx), cx);
+        self.selection.retain(|&idx| !concealed[idx]);
+        self.focused_box = self.focused_box.filter(|&idx| !concealed[idx]);
+        cx.notify();
+    }
+
+    fn toggle_frame_collapsed(
+        &mut self,
+        _: &ToggleFrameCollapsed,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let selection = self.selection.clone();
+        self.toggle_frames_collapsed(&selection, cx);
+    }
+
+    fn canvas_focused(&self, window: &Window) -> bool {
+        self.focus_handle.is_focused(window)
+    }
+
+    /// Drags the canvas along with the mouse while panning.
+    fn pan_with_mouse(&mut self, position: Point<Pixels>, cx: &mut Context<Self>) {
+        if let Some(last_pos) = self.last_mouse_pos {
+            let dx = position.x - last_pos.x;
+            let dy = position.y - last_pos.y;
+            self.viewport.center.x -= dx / self.viewport.zoom;
+            self.viewport.center.y -= dy / self.viewport.zoom;
+        }
+        self.last_mouse_pos = Some(position);
+        cx.notify();
+    }
+
+    fn pan_by(&mut self, dx: f32, dy: f32, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        self.viewport_animation = None;
+        self.viewport.center.x += px(dx) / self.viewport.zoom;
+        self.viewport.center.y += px(dy) / self.viewport.zoom;
+        cx.notify();
+    }
+
+    /// Arrow keys nudge the selection by one world unit, or pan the canvas
+    /// when nothing is selected.
+    fn pan_or_nudge(&mut self, dx: f32, dy: f32, window: &mut Window, cx: &mut Context<Self>) {
+        if self.selection.is_empty() {
+            self.pan_by(dx * PAN_STEP, dy * PAN_STEP, window, cx);
+        } else {
+            self.nudge_selection(dx, dy, window, cx);
+        }
+    }
+
+    fn pan_left(&mut self, _: &PanLeft, window: &mut Window, cx: &mut Context<Self>) {
+        self.pan_or_nudge(-1.0, 0.0, window, cx);
+    }
+
+    fn pan_right(&mut self, _: &PanRight, window: &mut Window, cx: &mut Context<Self>) {
+        self.pan_or_nudge(1.0, 0.0, window, cx);
+    }
+
+    fn pan_up(&mut self, _: &PanUp, window: &mut Window, cx: &mut Context<Self>) {
+        self.pan_or_nudge(0.0, -1.0, window, cx);
+    }
+
+    fn pan_down(&mut self, _: &PanDown, window: &mut Window, cx: &mut Context<Self>) {
+        self.pan_or_nudge(0.0, 1.0, window, cx);
+    }
+
+    fn nudge_selection(&mut self, dx: f32, dy: f32, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let parents = self.parents(cx);
+        // Boxes inside a selected frame move with the frame
+        let nudged: Vec<usize> = self
+            .selection
+            .iter()
+            .copied()
+            .filter(|&idx| {
+                idx < self.textboxes.len()
+                    && !self.is_locked(idx, cx)
+                    && !frame::any_ancestor(&parents, idx, |frame| self.selection.contains(&frame))
+            })
+            .collect();
+        if nudged.is_empty() {
+            return;
+        }
+        self.history.push(self.snapshot(cx));
+        let delta = point(px(dx), px(dy));
+        // The selection moves as a unit, so none of it pushes the rest
+        for &idx in &nudged {
+            for child in std::iter::once(idx).chain(self.carried_children(&parents, idx, cx)) {
+                self.translate_box(child, delta, cx);
+            }
+        }
+        self.last_move_direction = Some(delta);
+        self.handle_collisions(&nudged, cx);
+        cx.notify();
+    }
+
+    fn nudge_left_by_grid(
+        &mut self,
+        _: &NudgeLeftByGrid,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.nudge_selection(-GRID_STEP, 0.0, window, cx);
+    }
+
+    fn nudge_right_by_grid(
+        &mut self,
+        _: &NudgeRightByGrid,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.nudge_selection(GRID_STEP, 0.0, window, cx);
+    }
+
+    fn nudge_up_by_grid(&mut self, _: &NudgeUpByGrid, window: &mut Window, cx: &mut Context<Self>) {
+        self.nudge_selection(0.0, -GRID_STEP, window, cx);
+    }
+
+    fn nudge_down_by_grid(
+        &mut self,
+        _: &NudgeDownByGrid,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.nudge_selection(0.0, GRID_STEP, window, cx);
+    }
+
+    fn snapshot(&self, cx: &App) -> BoardSnapshot {
+        BoardSnapshot {
+            nodes: self
+                .textboxes
+                .iter()
+                .map(|textbox| textbox.read(cx).to_data(cx))
+                .collect(),
+        }
+    }
+
+    fn selection_snapshot(&self, cx: &App) -> BoardSnapshot {
+        BoardSnapshot {
+            nodes: self
+                .selection_with_contents(cx)
+                .iter()
+                .filter_map(|&idx| self.textboxes.get(idx))
+                .map(|textbox| textbox.read(cx).to_data(cx))
+                .collect(),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: BoardSnapshot, cx: &mut Context<Self>) {
+        self.textboxes.clear();
+        self.insert_nodes(&snapshot.nodes, cx);
+        let len = self.textboxes.len();
+        self.selection.retain(|&idx| idx < len);
+        self.focused_box = self.focused_box.filter(|&idx| idx < len);
+        // The restored boxes are new, with new text to search
+        self.refresh_search(cx);
+        cx.notify();
+    }
+
+    fn reindex_boxes(&self, cx: &mut Context<Self>) {
+        for (idx, textbox) in self.textboxes.iter().enumerate() {
+            textbox.update(cx, |textbox, _| textbox.index = idx);
+        }
+    }
+
+    /// Appends boxes for the given nodes and returns their indices.
+    fn insert_nodes(&mut self, nodes: &[NodeData], cx: &mut Context<Self>) -> Vec<usize> {
+        let start = self.textboxes.len();
+        for (offset, node) in nodes.iter().enumerate() {
+            let textbox = cx.new(|cx| DraggableTextBox::from_data(node, start + offset, cx));
+            Self::watch_textbox(&textbox, cx);
+            self.textboxes.push(textbox);
+        }
+        (start..self.textboxes.len()).collect()
+    }
+
+    /// Ends editing when the box's field loses focus, and listens for text
+    /// dragged out of it.
+    fn watch_textbox(textbox: &Entity<DraggableTextBox>, cx: &mut Context<Self>) {
+        let textfield = textbox.read(cx).textfield.clone();
+        cx.subscribe(&textfield, |this, textfield, event, cx| match event {
+            TextFieldEvent::Blurred => {
+                textfield.update(cx, |textfield, cx| textfield.set_interactive(false, cx))
+            }
+            TextFieldEvent::TextDropped {
+                range,
+                position,
+                copy,
+            } => this.drop_text(&textfield, range.clone(), *position, *copy, cx),
+            _ => {}
+        })
+        .detach();
+    }
+
+    /// Puts text dragged out of `source` into the box under the window
+    /// position `position`, or into a new box on empty canvas.
+    fn drop_text(
+        &mut self,
+        source: &Entity<TextField>,
+        range: Range<usize>,
+        position: Point<Pixels>,
+        copy: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(text) = source
+            .read(cx)
+            .text()
+            .get(range.clone())
+            .map(str::to_string)
+        else {
+            return;
+        };
+        let target = self.box_at(position, cx);
+        // Frames are empty canvas here, and the new box goes inside them
+        let frame = target.filter(|&idx| self.textboxes[idx].read(cx).is_frame());
+        match target.filter(|_| frame.is_none()) {
+            Some(idx) => {
+                let textbox = self.textboxes[idx].read(cx);
+                if textbox.image.is_some() || textbox.locked || textbox.textfield == *source {
+                    return;
+                }
+                let target = textbox.textfield.clone();
+                let snapshot = self.snapshot(cx);
+                let inserted = target.update(cx, |target, cx| {
+                    let offset = target.offset_for_position(position);
+                    target.insert_input(offset, &text, cx)
+                });
+                if !inserted {
+                    return;
+                }
+                self.history.push(snapshot);
+            }
+            None => {
+                let origin = self.viewport.inverse_transform_point(position);
+                let (width, height) = DEFAULT_BOX_SIZE;
+                let mut extent = Bounds::new(origin, size(px(width), px(height)));
+                if let Some(frame) = frame {
+                    extent = Self::fit_in_frame(extent, self.textboxes[frame].read(cx).extent());
+                }
+                self.add_nodes_as_selection(
+                    BoardSnapshot {
+                        nodes: vec![NodeData {
+                            text,
+                            x: f32::from(extent.origin.x),
+                            y: f32::from(extent.origin.y),
+                            width: f32::from(extent.size.width),
+                            height: f32::from(extent.size.height),
+                            ..Default::default()
+                        }],
+                    },
+                    cx,
+                );
+            }
+        }
+        if !copy && !source.read(cx).is_read_only() {
+            source.update(cx, |source, cx| source.replace_range(range, "", cx));
+        }
+    }
+
+    /// Pastes the primary selection into a box's text at a screen position.
+    fn paste_primary_into(&mut self, idx: usize, position: Point<Pixels>, cx: &mut Context<Self>) {
+        let textbox = self.textboxes[idx].read(cx);
+        if textbox.image.is_some() {
+            return;
+        }
+        let field = textbox.textfield.clone();
+        let snapshot = self.snapshot(cx);
+        if field.update(cx, |field, cx| field.paste_primary_at(position, cx)) {
+            self.history.push(snapshot);
+        }
+    }
+
+    /// `extent` shrunk and shifted as little as needed to sit inside the
+    /// body of a frame spanning `frame`, clear of its title bar and edges.
+    fn fit_in_frame(extent: Bounds<Pixels>, frame: Bounds<Pixels>) -> Bounds<Pixels> {
+        let left = f32::from(frame.left()) + FRAME_PADDING;
+        let top = f32::from(frame.top()) + FRAME_TITLE_HEIGHT + FRAME_PADDING;
+        let right = f32::from(frame.right()) - FRAME_PADDING;
+        let bottom = f32::from(frame.bottom()) - FRAME_PADDING;
+        let width = f32::from(extent.size.width).min(right - left).max(1.0);
+        let height = f32::from(extent.size.height).min(bottom - top).max(1.0);
+        let x = f32::from(extent.origin.x).min(right - width).max(left);
+        let y = f32::from(extent.origin.y).min(bottom - height).max(top);
+        Bounds::new(point(px(x), px(y)), size(px(width), px(height)))
+    }
+
+    fn remove_boxes(&mut self, indices: &[usize], cx: &mut Context<Self>) {
+        let mut idx = 0;
+        self.textboxes.retain(|_| {
+            let keep = !indices.contains(&idx);
+            idx += 1;
+            keep
+        });
+        self.reindex_boxes(cx);
+        self.selection.clear();
+        self.focused_box = None;
+        cx.notify();
+    }
+
+    fn copy_boxes(&mut self, _: &CopyBoxes, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let payload = self.selection_snapshot(cx);
+        if payload.nodes.is_empty() {
+            return;
+        }
+        cx.write_to_clipboard(ClipboardItem::new_string_with_json_metadata(
+            payload.plain_text(),
+            payload,
+        ));
+    }
+
+    fn cut_boxes(&mut self, _: &CutBoxes, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) || self.selection.is_empty() {
+            cx.propagate();
+            return;
+        }
+        self.copy_boxes(&CopyBoxes, window, cx);
+        self.history.push(self.snapshot(cx));
+        let selection = self.selection_with_contents(cx);
+        self.remove_boxes(&selection, cx);
+    }
+
+    fn apply_style_edit(&mut self, edit: StyleEdit, cx: &mut Context<Self>) {
+        self.update_selection_style(|style| style.apply(edit.clone()), cx);
+    }
+
+    fn update_selection_style(&mut self, update: impl Fn(&mut NodeStyle), cx: &mut Context<Self>) {
+        if self.selection.is_empty() {
+            return;
+        }
+        self.history.push(self.snapshot(cx));
+        for &idx in &self.selection {
+            if let Some(textbox) = self.textboxes.get(idx) {
+                textbox.update(cx, |textbox, cx| {
+                    let mut style = textbox.style.clone();
+                    update(&mut style);
+                    textbox.set_style(style, cx);
+                });
+            }
+        }
+        cx.notify();
+    }
+
+    /// Style shown in the inspector: the focused box's if it is selected,
+    /// otherwise the first selected box's.
+    fn inspected_style(&self, cx: &App) -> Option<NodeStyle> {
+        let idx = self
+            .focused_box
+            .filter(|idx| self.selection.contains(idx))
+            .or_else(|| self.selection.first().copied())?;
+        Some(self.textboxes.get(idx)?.read(cx).style.clone())
+    }
+
+    fn copy_style(&mut self, _: &CopyStyle, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(style) = self.inspected_style(cx) {
+            self.copied_style = Some(style);
+        }
+    }
+
+    fn paste_style(&mut self, _: &PasteStyle, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(copied) = self.copied_style.clone() {
+            self.update_selection_style(|style| *style = copied.clone(), cx);
+        }
+    }
+
+    fn paste_boxes(&mut self, _: &PasteBoxes, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let Some(item) = cx.read_from_clipboard() else {
+            return;
+        };
+        let mut payload = item
+            .entries()
+            .iter()
+            .find_map(|entry| match entry {
+                ClipboardEntry::String(string) => string.metadata_json::<BoardSnapshot>(),
+                _ => None,
+            })
+            .or_else(|| {
+                item.entries().iter().find_map(|entry| match entry {
+                    ClipboardEntry::Image(image) => {
+                        let kind = image_kind(image.format)?;
+                        let node = images::image_node_from_bytes(kind, image.bytes.clone(), 0., 0.);
+                        Some(BoardSnapshot { nodes: vec![node] })
+                    }
+                    _ => None,
+                })
+            })
+            .or_else(|| item.text().map(|text| Self::paragraph_column(&text)))
+            .unwrap_or_default();
+        let Some((x, y)) = payload.origin() else {
+            return;
+        };
+
+        // Place the pasted group's top-left corner under the mouse
+        let target = self
+            .viewport
+            .inverse_transform_point(window.mouse_position());
+        payload.translate(f32::from(target.x) - x, f32::from(target.y) - y);
+        self.add_nodes_as_selection(payload, cx);
+    }
+
+    /// Lays out one box per blank-line separated paragraph in a column.
+    fn paragraph_column(text: &str) -> BoardSnapshot {
+        let (width, height) = DEFAULT_BOX_SIZE;
+        let text = text.replace("\r\n", "\n");
+        let paragraphs = text
+            .split("\n\n")
+            .map(|paragraph| {
+                paragraph
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .filter(|paragraph| !paragraph.is_empty());
+
+        BoardSnapshot {
+            nodes: paragraphs
+                .enumerate()
+                .map(|(row, text)| NodeData {
+                    text,
+                    x: 0.0,
+                    y: row as f32 * (height + PASTE_COLUMN_GAP),
+                    width,
+                    height,
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    fn duplicate_boxes(&mut self, _: &DuplicateBoxes, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let mut payload = self.selection_snapshot(cx);
+        payload.translate(DUPLICATE_OFFSET, DUPLICATE_OFFSET);
+        self.add_nodes_as_selection(payload, cx);
+    }
+
+    fn add_nodes_as_selection(&mut self, payload: BoardSnapshot, cx: &mut Context<Self>) {
+        if payload.nodes.is_empty() {
+            return;
+        }
+        self.history.push(self.snapshot(cx));
+        self.selection = self.insert_nodes(&payload.nodes, cx);
+        self.focused_box = self.selection.first().copied();
+        cx.notify();
+    }
+
+    /// Adds an image box for each supported file, laid out in a row starting
+    /// at the given screen position.
+    fn insert_image_files(
+        &mut self,
+        paths: &[PathBuf],
+        screen_position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        let origin = self.viewport.inverse_transform_point(screen_position);
+        let mut x = f32::from(origin.x);
+        let mut nodes = Vec::new();
+        for path in paths {
+            if let Some(node) = images::image_node_from_path(path, x, f32::from(origin.y)) {
+                x += node.width + PASTE_COLUMN_GAP;
+                nodes.push(node);
+            }
+        }
+        self.add_nodes_as_selection(BoardSnapshot { nodes }, cx);
+    }
+
+    fn insert_image(&mut self, _: &InsertImage, window: &mut Window, cx: &mut Context<Self>) {
+        let window_size = window.viewport_size();
+        let center = point(window_size.width / 2.0, window_size.height / 2.0);
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
         });
+        cx.spawn(|this, mut cx| async move {
+            if let Ok(Ok(Some(paths))) = paths.await {
+                this.update(&mut cx, |this, cx| {
+                    this.insert_image_files(&paths, center, cx)
+                })
+                .ok();
+            }
+        })
+        .detach();
+    }
 
-        let textbox2 = cx.new(|cx| {
-            DraggableTextBox::new(
-                "Second Box".into(),
-                point(px(400.0), px(300.0)),
-                size(px(200.0), px(100.0)),
-                1,
-                cx,
-            )
+    /// Stores the selected images' bytes in the board instead of referencing
+    /// their files.
+    fn embed_images(&mut self, _: &EmbedImages, _: &mut Window, cx: &mut Context<Self>) {
+        let snapshot = self.snapshot(cx);
+        let mut changed = false;
+        for &idx in &self.selection {
+            let Some(textbox) = self.textboxes.get(idx) else {
+                continue;
+            };
+            textbox.update(cx, |textbox, cx| {
+                let embedded = textbox
+                    .image
+                    .as_ref()
+                    .and_then(|image| images::embed(&image.data));
+                if let Some(embedded) = embedded {
+                    textbox.image = Some(NodeImage::new(embedded));
+                    changed = true;
+                    cx.notify();
+                }
+            });
+        }
+        if changed {
+            self.history.push(snapshot);
+        }
+    }
+
+    fn save_board(&mut self, _: &SaveBoard, _: &mut Window, cx: &mut Context<Self>) {
+        let snapshot = self.snapshot(cx);
+        if let Some(path) = self.board_path.as_ref() {
+            if let Err(err) = snapshot.save(path) {
+                eprintln!("Failed to save board to {}: {err}", path.display());
+            }
+            return;
+        }
+
+        let directory = std::env::current_dir().unwrap_or_default();
+        let path = cx.prompt_for_new_path(&directory);
+        cx.spawn(|this, mut cx| async move {
+            let Ok(Ok(Some(path))) = path.await else {
+                return;
+            };
+            match snapshot.save(&path) {
+                Ok(()) => {
+                    this.update(&mut cx, |this, _| this.board_path = Some(path))
+                        .ok();
+                }
+                Err(err) => eprintln!("Failed to save board to {}: {err}", path.display()),
+            }
+        })
+        .detach();
+    }
+
+    fn open_board(&mut self, _: &OpenBoard, _: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
         });
+        cx.spawn(|this, mut cx| async move {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            match BoardSnapshot::load(&path) {
+                Ok(snapshot) => {
+                    this.update(&mut cx, |this, cx| {
+                        this.history = History::new(HISTORY_LIMIT);
+                        this.restore_snapshot(snapshot, cx);
+                        this.board_path = Some(path);
+                    })
+                    .ok();
+                }
+                Err(err) => eprintln!("Failed to open board {}: {err}", path.display()),
+            }
+        })
+        .detach();
+    }
 
-        Self {
-            textboxes: vec![textbox1, textbox2],
-            viewport: Viewport::new(),
-            is_dragging: None,
-            drag_offset: None,
-            is_panning: false,
-            last_mouse_pos: None,
-            focus_handle: cx.focus_handle(),
-            last_move_direction: None,
+    fn undo(&mut self, _: &Undo, window: &mut Window, cx: &mut Context<Self>) {
+        // Replacing is undone from the search bar it was started from
+        if !self.canvas_focused(window) && !self.search_bar_focused(window, cx) {
+            cx.propagate();
+            return;
+        }
+        if let Some(snapshot) = self.history.undo(self.snapshot(cx)) {
+            self.restore_snapshot(snapshot, cx);
         }
     }
 
-    fn update_textbox_position(
+    fn redo(&mut self, _: &Redo, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) && !self.search_bar_focused(window, cx) {
+            cx.propagate();
+            return;
+        }
+        if let Some(snapshot) = self.history.redo(self.snapshot(cx)) {
+            self.restore_snapshot(snapshot, cx);
+        }
+    }
+
+    /// Box indices sorted top-to-bottom, then left-to-right.
+    fn reading_order(&self, cx: &App) -> Vec<usize> {
+        let hidden = self.concealed(&self.parents(cx), cx);
+        let mut order: Vec<usize> = (0..self.textboxes.len())
+            .filter(|&idx| !hidden[idx])
+            .collect();
+        order.sort_by(|&a, &b| {
+            let a = self.textboxes[a].read(cx).position;
+            let b = self.textboxes[b].read(cx).position;
+            a.y.partial_cmp(&b.y)
+                .unwrap_or(Ordering::Equal)
+                .then(a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal))
+        });
+        order
+    }
+
+    fn focus_box(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        self.focused_box = Some(index);
+        self.selection = vec![index];
+
+        // Bring the box into view if it is not fully visible
+        if let Some(textbox) = self.textboxes.get(index) {
+            let bounds = textbox.read(cx).bounds();
+            let top_left = self.viewport.transform_point(bounds.origin);
+            let bottom_right = self.viewport.transform_point(bounds.bottom_right());
+            let window_size = window.viewport_size();
+            if top_left.x < px(0.0)
+                || top_left.y < px(0.0)
+                || bottom_right.x > window_size.width
+                || bottom_right.y > window_size.height
+            {
+                let center = bounds.center();
+                self.viewport.center = point(
+                    center.x - window_size.width / 2.0 / self.viewport.zoom,
+                    center.y - window_size.height / 2.0 / self.viewport.zoom,
+                );
+            }
+        }
+        cx.notify();
+    }
+
+    fn focus_in_reading_order(
         &mut self,
-        index: usize,
-        new_position: Point<Pixels>,
+        forward: bool,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if let Some(textbox) = self.textboxes.get(index) {
-            textbox.update(cx, |textbox, cx| {
-                let old_position = textbox.position;
-                textbox.position = new_position;
-                cx.notify();
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let order = self.reading_order(cx);
+        if order.is_empty() {
+            return;
+        }
+        let current = self
+            .focused_box
+            .and_then(|focused| order.iter().position(|&idx| idx == focused));
+        let next = Self::cycle_position(current, order.len(), forward);
+        self.focus_box(order[next], window, cx);
+    }
+
+    /// Position after `current` in a cycle of `len`, starting from either
+    /// end when there's no current one.
+    fn cycle_position(current: Option<usize>, len: usize, forward: bool) -> usize {
+        match (current, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(pos), true) => (pos + 1) % len,
+            (Some(pos), false) => (pos + len - 1) % len,
+        }
+    }
+
+    fn focus_next_box(&mut self, _: &FocusNextBox, window: &mut Window, cx: &mut Context<Self>) {
+        self.focus_in_reading_order(true, window, cx);
+    }
+
+    fn focus_previous_box(
+        &mut self,
+        _: &FocusPreviousBox,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.focus_in_reading_order(false, window, cx);
+    }
+
+    /// Moves focus to the nearest box in the given direction, preferring boxes
+    /// that are closely aligned with the currently focused one.
+    fn focus_in_direction(
+        &mut self,
+        direction: Point<f32>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        let Some(current) = self.focused_box else {
+            self.focus_in_reading_order(true, window, cx);
+            return;
+        };
+        let Some(origin) = self
+            .textboxes
+            .get(current)
+            .map(|textbox| textbox.read(cx).bounds().center())
+        else {
+            return;
+        };
 
-                let move_delta = point(
-                    new_position.x - old_position.x,
-                    new_position.y - old_position.y,
+        let best = self
+            .textboxes
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != current)
+            .filter_map(|(idx, textbox)| {
+                let center = textbox.read(cx).bounds().center();
+                let offset = point(
+                    f32::from(center.x - origin.x),
+                    f32::from(center.y - origin.y),
                 );
-                self.last_move_direction = Some(move_delta);
-            });
+                Self::direction_score(offset, direction).map(|score| (idx, score))
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        if let Some((idx, _)) = best {
+            self.focus_box(idx, window, cx);
+        }
+    }
+
+    /// How far away a box `offset` from the focused one is when heading in
+    /// `direction`, counting drift to the side double, or `None` if it's
+    /// not ahead at all.
+    fn direction_score(offset: Point<f32>, direction: Point<f32>) -> Option<f32> {
+        let along = offset.x * direction.x + offset.y * direction.y;
+        let across = (offset.x * direction.y - offset.y * direction.x).abs();
+        (along > 0.0).then_some(along + across * 2.0)
+    }
+
+    fn focus_box_left(&mut self, _: &FocusBoxLeft, window: &mut Window, cx: &mut Context<Self>) {
+        self.focus_in_direction(point(-1.0, 0.0), window, cx);
+    }
+
+    fn focus_box_right(&mut self, _: &FocusBoxRight, window: &mut Window, cx: &mut Context<Self>) {
+        self.focus_in_direction(point(1.0, 0.0), window, cx);
+    }
+
+    fn focus_box_up(&mut self, _: &FocusBoxUp, window: &mut Window, cx: &mut Context<Self>) {
+        self.focus_in_direction(point(0.0, -1.0), window, cx);
+    }
+
+    fn focus_box_down(&mut self, _: &FocusBoxDown, window: &mut Window, cx: &mut Context<Self>) {
+        self.focus_in_direction(point(0.0, 1.0), window, cx);
+    }
+
+    /// Drops the selection and box focus, so arrow keys pan again.
+    fn clear_selection(&mut self, _: &ClearSelection, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        self.selection.clear();
+        self.focused_box = None;
+        cx.notify();
+    }
+
+    fn edit_focused_box(
+        &mut self,
+        _: &EditFocusedBox,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.canvas_focused(window) {
+            cx.propagate();
+            return;
+        }
+        if let Some(idx) = self.focused_box {
+            self.start_editing(idx, window, cx);
+        }
+    }
+
+    /// Hands the mouse and keyboard to a box's text, until it loses focus.
+    /// Returns the box's text field, or `None` for image boxes.
+    fn start_editing(
+        &mut self,
+        idx: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<TextField>> {
+        let textbox = self.textboxes.get(idx)?.read(cx);
+        if textbox.image.is_some() {
+            return None;
+        }
+        let textfield = textbox.textfield.clone();
+        textfield.update(cx, |textfield, cx| {
+            textfield.set_interactive(true, cx);
+            textfield.focus(window, cx);
+        });
+        self.focused_box = Some(idx);
+        self.selection = vec![idx];
+        cx.notify();
+        Some(textfield)
+    }
+
+    fn search_bar_focused(&self, window: &Window, cx: &App) -> bool {
+        self.search_field.focus_handle(cx).is_focused(window)
+            || self.replace_field.focus_handle(cx).is_focused(window)
+    }
+
+    fn stop_editing(&mut self, _: &StopEditing, window: &mut Window, cx: &mut Context<Self>) {
+        if self.search_bar_focused(window, cx) {
+            self.close_search(&CloseSearch, window, cx);
+            return;
+        }
+        // Keep the edited box focused so traversal continues from it
+        if let Some(idx) = self.textboxes.iter().position(|textbox| {
+            textbox
+                .read(cx)
+                .textfield
+                .focus_handle(cx)
+                .is_focused(window)
+        }) {
+            self.focused_box = Some(idx);
+        }
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
 
-            self.handle_collisions(index, cx);
+    fn open_search(&mut self, _: &OpenSearch, window: &mut Window, cx: &mut Context<Self>) {
+        if self.search.is_none() {
+            self.search = Some(SearchState::default());
+            self.refresh_search(cx);
         }
+        self.search_field
+            .update(cx, |field, cx| field.focus(window, cx));
+        cx.notify();
+    }
+
+    fn close_search(&mut self, _: &CloseSearch, window: &mut Window, cx: &mut Context<Self>) {
+        self.search = None;
+        self.apply_search_highlights(cx);
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn toggle_replace(&mut self, _: &ToggleReplace, window: &mut Window, cx: &mut Context<Self>) {
+        let search = self.search.get_or_insert_with(SearchState::default);
+        search.replacing = !search.replacing;
+        let field = if search.replacing {
+            self.replace_field.clone()
+        } else {
+            self.search_field.clone()
+        };
+        field.update(cx, |field, cx| field.focus(window, cx));
+        self.refresh_search(cx);
     }
 
-    fn handle_collisions(&mut self, moving_idx: usize, cx: &mut Context<Self>) {
-        if let Some(move_delta) = self.last_move_direction {
-            let mut boxes_to_move = Vec::new();
+    fn toggle_search_case_sensitive(
+        &mut self,
+        _: &ToggleSearchCaseSensitive,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.search_options.case_sensitive = !self.search_options.case_sensitive;
+        self.refresh_search(cx);
+    }
 
-            if let Some(moving_box) = self.textboxes.get(moving_idx) {
-                let moving_box_data = moving_box.read(cx);
+    fn toggle_search_regex(
+        &mut self,
+        _: &ToggleSearchRegex,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.search_options.regex = !self.search_options.regex;
+        self.refresh_search(cx);
+    }
 
-                for (idx, other_box) in self.textboxes.iter().enumerate() {
-                    if idx != moving_idx {
-                        let other_box_data = other_box.read(cx);
-                        if moving_box_data.overlaps(&other_box_data) {
-                            boxes_to_move.push(idx);
+    /// Reruns the query against every visible box, in reading order. The
+    /// current match is kept if the query didn't change.
+    fn refresh_search(&mut self, cx: &mut Context<Self>) {
+        let Some(previous) = self.search.take() else {
+            return;
+        };
+        let query = self.search_field.read(cx).text().to_string();
+        let mut search = SearchState {
+            query,
+            replacing: previous.replacing,
+            ..Default::default()
+        };
+        if !search.query.is_empty() {
+            match SearchQuery::new(&search.query, self.search_options) {
+                Ok(compiled) => {
+                    for idx in self.reading_order(cx) {
+                        let textbox = self.textboxes[idx].read(cx);
+                        if textbox.image.is_some() {
+                            continue;
                         }
+                        let text = textbox.textfield.read(cx).text();
+                        search
+                            .matches
+                            .extend(compiled.find_in(text).into_iter().map(|range| SearchMatch {
+                                box_idx: idx,
+                                range,
+                            }));
                     }
+                    search.compiled = Some(compiled);
                 }
+                Err(err) => search.error = Some(err),
             }
+        }
+        if search.query == previous.query {
+            search.current = previous
+                .current
+                .filter(|&current| current < search.matches.len());
+        }
+        self.search = Some(search);
+        self.apply_search_highlights(cx);
+        cx.notify();
+    }
 
-            for idx in boxes_to_move {
-                if let Some(box_to_move) = self.textboxes.get(idx) {
-                    box_to_move.update(cx, |textbox, cx| {
-                        textbox.position = point(
-                            textbox.position.x + move_delta.x,
-                            textbox.position.y + move_delta.y,
-                        );
-                        cx.notify();
-                    });
-                    self.handle_collisions(idx, cx);
+    /// Marks each box's matches in its text field, or clears them when the
+    /// search is closed.
+    fn apply_search_highlights(&self, cx: &mut Context<Self>) {
+        for (idx, textbox) in self.textboxes.iter().enumerate() {
+            let mut highlights = Vec::new();
+            let mut active = None;
+            if let Some(search) = &self.search {
+                for (match_idx, found) in search.matches.iter().enumerate() {
+                    if found.box_idx == idx {
+                        if search.current == Some(match_idx) {
+                            active = Some(highlights.len());
+                        }
+                        highlights.push(found.range.clone());
+                    }
+                }
+            }
+            let textfield = textbox.read(cx).textfield.clone();
+            textfield.update(cx, |textfield, cx| {
+                textfield.set_highlights(highlights, active, cx)
+            });
+        }
+    }
+
+    fn step_search(&mut self, forward: bool, window: &mut Window, cx: &mut Context<Self>) {
+        // Boxes may have been edited since the last search
+        self.refresh_search(cx);
+        let Some(search) = self.search.as_ref() else {
+            return;
+        };
+        let count = search.matches.len();
+        if count == 0 {
+            return;
+        }
+        let next = match (search.current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+        };
+        self.jump_to_match(next, window, cx);
+    }
+
+    /// Makes match `index` the current one and brings its box into view.
+    fn jump_to_match(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.current = Some(index);
+        let box_idx = search.matches[index].box_idx;
+
+        self.selection = vec![box_idx];
+        self.focused_box = Some(box_idx);
+        self.apply_search_highlights(cx);
+        let center = self.textboxes[box_idx].read(cx).bounds().center();
+        self.animate_viewport_to(center, window, cx);
+    }
+
+    fn search_next(&mut self, _: &SearchNext, window: &mut Window, cx: &mut Context<Self>) {
+        self.step_search(true, window, cx);
+    }
+
+    fn search_previous(&mut self, _: &SearchPrevious, window: &mut Window, cx: &mut Context<Self>) {
+        self.step_search(false, window, cx);
+    }
+
+    /// Text typed in the replacement field.
+    fn replacement(&self, cx: &App) -> String {
+        self.replace_field.read(cx).text().to_string()
+    }
+
+    /// Boxes with at least one match, in reading order.
+    fn boxes_with_matches(&self) -> Vec<usize> {
+        let mut boxes: Vec<usize> = Vec::new();
+        if let Some(search) = &self.search {
+            for found in &search.matches {
+                if boxes.last() != Some(&found.box_idx) {
+                    boxes.push(found.box_idx);
                 }
             }
         }
+        boxes
+    }
+
+    /// Replaces the current match, or the first one, and moves on to the
+    /// match after it.
+    fn replace_one(&mut self, _: &ReplaceOne, window: &mut Window, cx: &mut Context<Self>) {
+        self.refresh_search(cx);
+        let Some(search) = &self.search else {
+            return;
+        };
+        let Some(compiled) = search.compiled.clone() else {
+            return;
+        };
+        if search.matches.is_empty() {
+            return;
+        }
+        let index = search.current.unwrap_or(0);
+        let found = search.matches[index].clone();
+        let textfield = self.textboxes[found.box_idx].read(cx).textfield.clone();
+        let text = textfield.read(cx).text().to_string();
+        let Some(replacement) =
+            compiled.replacement_for(&text, found.range.clone(), &self.replacement(cx))
+        else {
+            return;
+        };
+
+        self.history.push(self.snapshot(cx));
+        textfield.update(cx, |textfield, cx| {
+            textfield.replace_range(found.range, &replacement, cx)
+        });
+        // The match that followed the replaced one now has its index
+        self.refresh_search(cx);
+        let count = self
+            .search
+            .as_ref()
+            .map_or(0, |search| search.matches.len());
+        if count > 0 {
+            self.jump_to_match(index % count, window, cx);
+        }
+    }
+
+    /// Replaces every match on the board as a single undo step.
+    fn replace_all(&mut self, _: &ReplaceAll, _: &mut Window, cx: &mut Context<Self>) {
+        self.refresh_search(cx);
+        let Some(compiled) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.compiled.clone())
+        else {
+            return;
+        };
+        let boxes = self.boxes_with_matches();
+        if boxes.is_empty() {
+            return;
+        }
+        let replacement = self.replacement(cx);
+
+        self.history.push(self.snapshot(cx));
+        for idx in boxes {
+            let textfield = self.textboxes[idx].read(cx).textfield.clone();
+            let text = textfield.read(cx).text().to_string();
+            let replaced = compiled.replace_all(&text, &replacement);
+            textfield.update(cx, |textfield, cx| textfield.set_text(replaced, cx));
+        }
+        self.refresh_search(cx);
+    }
+
+    /// Each affected box's text before and after a replace-all.
+    fn replace_preview(&self, cx: &App) -> Vec<(SharedString, SharedString)> {
+        let Some(compiled) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.compiled.as_ref())
+        else {
+            return Vec::new();
+        };
+        let replacement = self.replacement(cx);
+        self.boxes_with_matches()
+            .into_iter()
+            .map(|idx| {
+                let text = self.textboxes[idx].read(cx).textfield.read(cx).text();
+                (
+                    text.clone(),
+                    compiled.replace_all(text, &replacement).into(),
+                )
+            })
+            .collect()
+    }
+
+    /// Glides the viewport until the world point `target` is in the middle
+    /// of the window.
+    fn animate_viewport_to(
+        &mut self,
+        target: Point<Pixels>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let window_size = window.viewport_size();
+        self.viewport_animation = Some(ViewportAnimation {
+            from: self.viewport.center,
+            to: point(
+                target.x - window_size.width / 2.0 / self.viewport.zoom,
+                target.y - window_size.height / 2.0 / self.viewport.zoom,
+            ),
+            start: Instant::now(),
+        });
+        cx.notify();
+    }
+
+    /// Moves the viewport along its running animation, asking for another
+    /// frame until it arrives.
+    fn advance_viewport_animation(&mut self, window: &mut Window) {
+        let Some(animation) = self.viewport_animation else {
+            return;
+        };
+        let progress = (animation.start.elapsed().as_secs_f32()
+            / VIEWPORT_ANIMATION_DURATION.as_secs_f32())
+        .min(1.0);
+        // Ease out so the glide settles gently on the match
+        let eased = 1.0 - (1.0 - progress).powi(3);
+        self.viewport.center = point(
+            animation.from.x + (animation.to.x - animation.from.x) * eased,
+            animation.from.y + (animation.to.y - animation.from.y) * eased,
+        );
+        if progress < 1.0 {
+            window.request_animation_frame();
+        } else {
+            self.viewport_animation = None;
+        }
     }
 }
 
@@ -111,39 +1913,138 @@ impl Focusable for ViewportApp {
 }
 
 impl Render for ViewportApp {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let textbox_data: Vec<_> = self
-            .textboxes
-            .iter()
-            .enumerate()
-            .map(|(idx, textbox)| {
-                let tb = textbox.read(cx);
-                (
-                    idx,
-                    tb.textfield.clone(),
-                    self.viewport.transform_point(tb.position),
-                    self.viewport.transform_size(tb.size),
-                )
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.advance_viewport_animation(window);
+        let focused_box = self.focused_box;
+        let selection = self.selection.clone();
+        let dragging = self.is_dragging;
+        let parents = self.parents(cx);
+        let hidden = self.concealed(&parents, cx);
+        let is_hidden = |idx: usize| self.textboxes[idx].read(cx).hidden;
+        // Shown hidden boxes fade, along with everything inside them
+        let textboxes: Vec<_> = self
+            .paint_order(cx)
+            .into_iter()
+            .filter(|&idx| !hidden[idx])
+            .map(|idx| {
+                let faded = is_hidden(idx) || frame::any_ancestor(&parents, idx, is_hidden);
+                (idx, self.textboxes[idx].read(cx).clone(), faded)
             })
             .collect();
 
         let viewport = self.viewport;
+        let detail = Settings::get(cx).level_of_detail.at_zoom(viewport.zoom);
+        let inspected_style = self.inspected_style(cx);
+        let search_bar = self.search.as_ref().map(|search| {
+            let status = match (&search.error, search.current) {
+                (Some(_), _) => String::new(),
+                (None, Some(current)) => format!("{} of {}", current + 1, search.matches.len()),
+                (None, None) if search.query.is_empty() => String::new(),
+                (None, None) if search.matches.is_empty() => "No results".into(),
+                (None, None) => format!("{} found", search.matches.len()),
+            };
+            SearchBar::new(self.search_field.clone(), self.search_options)
+                .status(status)
+                .error(search.error.clone().map(Into::into))
+                .when(search.replacing, |bar| {
+                    bar.replace(self.replace_field.clone(), self.replace_preview(cx))
+                })
+        });
 
         div()
             .size_full()
-            .bg(rgb(0xEEEEEE))
+            .bg(Theme::get(cx).canvas_background)
             .flex()
             .key_context("viewport_app")
             .track_focus(&self.focus_handle(cx))
             .id("viewport_app")
+            .on_action(cx.listener(Self::pan_left))
+            .on_action(cx.listener(Self::pan_right))
+            .on_action(cx.listener(Self::pan_up))
+            .on_action(cx.listener(Self::pan_down))
+            .on_action(cx.listener(Self::focus_next_box))
+            .on_action(cx.listener(Self::focus_previous_box))
+            .on_action(cx.listener(Self::focus_box_left))
+            .on_action(cx.listener(Self::focus_box_right))
+            .on_action(cx.listener(Self::focus_box_up))
+            .on_action(cx.listener(Self::focus_box_down))
+            .on_action(cx.listener(Self::edit_focused_box))
+            .on_action(cx.listener(Self::stop_editing))
+            .on_action(cx.listener(Self::clear_selection))
+            .on_action(cx.listener(Self::nudge_left_by_grid))
+            .on_action(cx.listener(Self::nudge_right_by_grid))
+            .on_action(cx.listener(Self::nudge_up_by_grid))
+            .on_action(cx.listener(Self::nudge_down_by_grid))
+            .on_action(cx.listener(Self::undo))
+            .on_action(cx.listener(Self::redo))
+            .on_action(cx.listener(Self::copy_boxes))
+            .on_action(cx.listener(Self::cut_boxes))
+            .on_action(cx.listener(Self::paste_boxes))
+            .on_action(cx.listener(Self::duplicate_boxes))
+            .on_action(cx.listener(Self::insert_image))
+            .on_action(cx.listener(Self::embed_images))
+            .on_action(cx.listener(Self::save_board))
+            .on_action(cx.listener(Self::open_board))
+            .on_action(cx.listener(Self::set_shape_rectangle))
+            .on_action(cx.listener(Self::set_shape_rounded))
+            .on_action(cx.listener(Self::set_shape_ellipse))
+            .on_action(cx.listener(Self::set_shape_diamond))
+            .on_action(cx.listener(Self::set_shape_sticky_note))
+            .on_action(cx.listener(Self::copy_style))
+            .on_action(cx.listener(Self::paste_style))
+            .on_action(cx.listener(Self::bring_forward))
+            .on_action(cx.listener(Self::send_backward))
+            .on_action(cx.listener(Self::bring_to_front))
+            .on_action(cx.listener(Self::send_to_back))
+            .on_action(cx.listener(Self::toggle_collisions))
+            .on_action(cx.listener(Self::frame_selection))
+            .on_action(cx.listener(Self::toggle_frame_collapsed))
+            .on_action(cx.listener(Self::toggle_locked))
+            .on_action(cx.listener(Self::toggle_hidden))
+            .on_action(cx.listener(Self::toggle_show_hidden))
+            .on_action(cx.listener(Self::open_search))
+            .on_action(cx.listener(Self::close_search))
+            .on_action(cx.listener(Self::search_next))
+            .on_action(cx.listener(Self::search_previous))
+            .on_action(cx.listener(Self::toggle_replace))
+            .on_action(cx.listener(Self::replace_one))
+            .on_action(cx.listener(Self::replace_all))
+            .on_action(cx.listener(Self::toggle_search_case_sensitive))
+            .on_action(cx.listener(Self::toggle_search_regex))
+            .on_drop(cx.listener(|this, paths: &ExternalPaths, window, cx| {
+                this.insert_image_files(paths.paths(), window.mouse_position(), cx);
+            }))
             .on_drag(DragState, move |_this, offset, _window, cx| {
                 println!("Canvas dragged with offset: {:?}", offset);
                 cx.new(|_| EmptyView {})
             })
             .on_mouse_down(
                 MouseButton::Left,
-                cx.listener(|this, event: &MouseDownEvent, _window, cx| {
-                    if this.is_dragging.is_none() {
+                cx.listener(|this, event: &MouseDownEvent, window, cx| {
+                    if this.is_resizing.is_some() {
+                        return;
+                    }
+                    // Clicks on a box select and drag it; a double-click
+                    // puts the caret in its text instead
+                    if let Some(idx) = this.box_at(event.position, cx) {
+                        if event.click_count == 2 {
+                            if let Some(textfield) = this.start_editing(idx, window, cx) {
+                                textfield.update(cx, |textfield, cx| {
+                                    let offset = textfield.offset_for_position(event.position);
+                                    textfield.select_range(offset..offset, cx);
+                                });
+                                return;
+                            }
+                        }
+                        // Takes focus from any box still being edited, which
+                        // ends its editing
+                        window.focus(&this.focus_handle);
+                        this.start_box_drag(idx, event, cx);
+                    } else {
+                        window.focus(&this.focus_handle);
+                        this.focused_box = None;
+                        this.selection.clear();
+                        this.viewport_animation = None;
                         this.is_panning = true;
                         this.last_mouse_pos = Some(event.position);
                         cx.notify();
@@ -152,28 +2053,67 @@ impl Render for ViewportApp {
             )
             .on_drag_move(
                 cx.listener(|this, event: &DragMoveEvent<DragState>, _window, cx| {
-                    if let Some(drag_idx) = this.is_dragging {
+                    if let Some(resize_idx) = this.is_resizing {
+                        let corner = this.viewport.inverse_transform_point(event.event.position);
+                        this.resize_textbox(resize_idx, corner, cx);
+                    } else if let Some(drag_idx) = this.is_dragging {
                         if let Some(offset) = this.drag_offset {
                             let screen_pos = event.event.position;
                             let new_screen_pos =
                                 point(screen_pos.x - offset.x, screen_pos.y - offset.y);
                             let new_position =
                                 this.viewport.inverse_transform_point(new_screen_pos);
-                            this.update_textbox_position(drag_idx, new_position, cx);
+                            let children = this.drag_children.clone();
+                            this.update_textbox_position(drag_idx, new_position, &children, cx);
                         }
                     } else if this.is_panning {
-                        if let Some(last_pos) = this.last_mouse_pos {
-                            let dx = event.event.position.x - last_pos.x;
-                            let dy = event.event.position.y - last_pos.y;
-                            this.viewport.center.x -= dx / this.viewport.zoom;
-                            this.viewport.center.y -= dy / this.viewport.zoom;
+                        this.pan_with_mouse(event.event.position, cx);
+                    }
+                }),
+            )
+            // Middle-drag pans from anywhere on empty canvas, and a middle
+            // click on a box pastes into it
+            .on_mouse_down(
+                MouseButton::Middle,
+                cx.listener(|this, event: &MouseDownEvent, _window, cx| {
+                    let target = this
+                        .box_at(event.position, cx)
+                        .filter(|&idx| !this.textboxes[idx].read(cx).is_frame());
+                    match target {
+                        Some(idx) => this.paste_primary_into(idx, event.position, cx),
+                        None => {
+                            this.viewport_animation = None;
+                            this.is_panning = true;
+                            this.last_mouse_pos = Some(event.position);
                         }
-                        this.last_mouse_pos = Some(event.event.position);
-                        cx.notify();
                     }
                 }),
             )
+            .on_mouse_move(cx.listener(|this, event: &MouseMoveEvent, _window, cx| {
+                // Left-button pans arrive as drag moves instead
+                if this.is_panning && event.pressed_button == Some(MouseButton::Middle) {
+                    this.pan_with_mouse(event.position, cx);
+                }
+            }))
+            .on_mouse_up(
+                MouseButton::Middle,
+                cx.listener(|this, _: &MouseUpEvent, _window, cx| {
+                    this.is_panning = false;
+                    this.last_mouse_pos = None;
+                    cx.notify();
+                }),
+            )
+            // The pointer can drift off the canvas mid-pan
+            .on_mouse_up_out(
+                MouseButton::Middle,
+                cx.listener(|this, _: &MouseUpEvent, _window, cx| {
+                    this.is_panning = false;
+                    this.last_mouse_pos = None;
+                    cx.notify();
+                }),
+            )
             .on_scroll_wheel(cx.listener(|this, event: &ScrollWheelEvent, _window, cx| {
+                this.viewport_animation = None;
                 let old_mouse_world = this.viewport.inverse_transform_point(event.position);
                 match event.delta {
                     ScrollDelta::Lines(delta) => {
@@ -193,41 +2133,139 @@ impl Render for ViewportApp {
             .on_mouse_up(
                 MouseButton::Left,
                 cx.listener(|this, _: &MouseUpEvent, _window, cx| {
+                    if let Some(snapshot) = this.drag_start_snapshot.take() {
+                        if snapshot != this.snapshot(cx) {
+                            this.history.push(snapshot);
+                        }
+                    }
                     this.is_dragging = None;
+                    this.drag_children.clear();
                     this.drag_offset = None;
+                    this.is_resizing = None;
+                    this.resize_aspect = None;
                     this.is_panning = false;
                     this.last_mouse_pos = None;
                     cx.notify();
                 }),
             )
-            .children(
-                textbox_data
-                    .into_iter()
-                    .map(move |(idx, textfield, pos, size)| {
-                        div()
-                            .absolute()
-                            .left(pos.x)
-                            .top(pos.y)
-                            .w(size.width)
-                            .h(size.height)
-                            .bg(rgb(0x2D3142))
-                            .text_color(rgb(0xFFFFFF))
-                            .text_size(px(16.0 * viewport.zoom))
-                            .cursor(CursorStyle::OpenHand)
-                            .id(("textbox", idx))
-                            .on_mouse_down(
-                                MouseButton::Left,
-                                cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
-                                    this.is_dragging = Some(idx);
-                                    this.drag_offset = Some(point(
-                                        event.position.x - pos.x,
-                                        event.position.y - pos.y,
-                                    ));
-                                    cx.notify();
-                                }),
-                            )
-                            .child(textfield)
+            .children(textboxes.into_iter().map(|(idx, textbox, faded)| {
+                textbox
+                    .element(&viewport)
+                    .selected(selection.contains(&idx))
+                    .focused(focused_box == Some(idx))
+                    .dragging(dragging == Some(idx))
+                    .locked(textbox.locked)
+                    .faded(faded)
+                    // Keep the focused box editable however far out we zoom
+                    .detail(if focused_box == Some(idx) {
+                        LevelOfDetail::Full
+                    } else {
+                        detail
+                    })
+                    .on_resize_start(cx.listener(
+                        move |this, event: &MouseDownEvent, _window, cx| {
+                            // A box painted above this one covers its handle
+                            if this.box_or_handle_at(event.position, cx) != Some(idx)
+                                || this.is_locked(idx, cx)
+                            {
+                                return;
+                            }
+                            this.is_resizing = Some(idx);
+                            this.resize_aspect = this
+                                .textboxes
+                                .get(idx)
+                                .map(|textbox| textbox.read(cx))
+                                .filter(|textbox| textbox.image.is_some())
+                                .map(|textbox| textbox.size.width / textbox.size.height);
+                            this.drag_start_snapshot = Some(this.snapshot(cx));
+                            this.selection = vec![idx];
+                            this.focused_box = Some(idx);
+                            cx.notify();
+                        },
+                    ))
+                    .on_toggle_collapsed(cx.listener(move |this, _: &ClickEvent, _window, cx| {
+                        this.toggle_frames_collapsed(&[idx], cx)
+                    }))
+            }))
+            .when_some(inspected_style, |div, style| {
+                div.child(StyleInspector::new(
+                    style,
+                    cx.listener(|this, edit: &StyleEdit, _window, cx| {
+                        this.apply_style_edit(edit.clone(), cx)
                     }),
-            )
+                ))
+            })
+            .when_some(search_bar, |div, search_bar| div.child(search_bar))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_cycles_around_both_ends() {
+        assert_eq!(ViewportApp::cycle_position(None, 3, true), 0);
+        assert_eq!(ViewportApp::cycle_position(None, 3, false), 2);
+        assert_eq!(ViewportApp::cycle_position(Some(2), 3, true), 0);
+        assert_eq!(ViewportApp::cycle_position(Some(0), 3, false), 2);
+    }
+
+    #[test]
+    fn aligned_boxes_win_over_closer_ones_off_to_the_side() {
+        let right = point(1.0, 0.0);
+        assert_eq!(ViewportApp::direction_score(point(-10.0, 0.0), right), None);
+        let aligned = ViewportApp::direction_score(point(100.0, 0.0), right).unwrap();
+        let diagonal = ViewportApp::direction_score(point(50.0, 40.0), right).unwrap();
+        assert!(aligned < diagonal);
+    }
+
+    #[test]
+    fn stepping_passes_one_unselected_neighbour() {
+        assert_eq!(ViewportApp::stepped_order(4, &[0, 2], true), [1, 0, 3, 2]);
+        assert_eq!(ViewportApp::stepped_order(4, &[1, 3], false), [1, 0, 3, 2]);
+        // Selected boxes already at the end stay there, with their order kept
+        assert_eq!(ViewportApp::stepped_order(3, &[1, 2], true), [0, 1, 2]);
+    }
+
+    #[test]
+    fn selection_moves_to_either_end_in_order() {
+        assert_eq!(ViewportApp::order_with_ends(4, &[2, 0], true), [1, 3, 0, 2]);
+        assert_eq!(
+            ViewportApp::order_with_ends(4, &[3, 1], false),
+            [1, 3, 0, 2]
+        );
+    }
+
+    #[test]
+    fn dropped_boxes_fit_inside_their_frame() {
+        let frame = Bounds::new(point(px(0.0), px(0.0)), size(px(300.0), px(200.0)));
+        let dropped = Bounds::new(point(px(250.0), px(150.0)), size(px(200.0), px(100.0)));
+        let corner = point(px(300.0 - FRAME_PADDING), px(200.0 - FRAME_PADDING));
+        let fitted = ViewportApp::fit_in_frame(dropped, frame);
+        assert_eq!(fitted.size, dropped.size);
+        assert_eq!(fitted.bottom_right(), corner);
+
+        // Too big for the frame's body, so it shrinks to fill it
+        let dropped = Bounds::new(point(px(-50.0), px(0.0)), size(px(400.0), px(400.0)));
+        let fitted = ViewportApp::fit_in_frame(dropped, frame);
+        let body_top = FRAME_TITLE_HEIGHT + FRAME_PADDING;
+        assert_eq!(fitted.origin, point(px(FRAME_PADDING), px(body_top)));
+        assert_eq!(fitted.bottom_right(), corner);
+    }
+
+    #[test]
+    fn paragraphs_become_a_column_of_boxes() {
+        let column = ViewportApp::paragraph_column("one\r\ntwo\r\n\r\n\n\n  three  \n");
+        let texts: Vec<_> = column.nodes.iter().map(|node| node.text.as_str()).collect();
+        assert_eq!(texts, ["one two", "three"]);
+        let (_, height) = DEFAULT_BOX_SIZE;
+        assert_eq!(column.nodes[0].y, 0.0);
+        assert_eq!(column.nodes[1].y, height + PASTE_COLUMN_GAP);
+    }
+
+    #[test]
+    fn blank_text_makes_no_boxes() {
+        assert!(ViewportApp::paragraph_column(" \n\n \n").nodes.is_empty());
     }
 }
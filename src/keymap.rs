@@ -0,0 +1,106 @@
+use crate::components::{text_field::*, viewport::*};
+use gpui::*;
+
+/// Modifier for the main shortcuts, written `primary` in the tables below.
+#[cfg(target_os = "macos")]
+const PRIMARY: &str = "cmd";
+#[cfg(not(target_os = "macos"))]
+const PRIMARY: &str = "ctrl";
+
+/// Binds the default shortcuts, using cmd on macOS and ctrl elsewhere.
+pub fn bind_default_keys(cx: &mut App) {
+    // Text editing
+    cx.bind_keys([
+        bind("backspace", Backspace, "TextField"),
+        bind("delete", Delete, "TextField"),
+        bind("left", Left, "TextField"),
+        bind("right", Right, "TextField"),
+        bind("shift-left", SelectLeft, "TextField"),
+        bind("shift-right", SelectRight, "TextField"),
+        bind("primary-a", SelectAll, "TextField"),
+        bind("primary-v", Paste, "TextField"),
+        bind("primary-c", Copy, "TextField"),
+        bind("primary-x", Cut, "TextField"),
+        bind("home", Home, "TextField"),
+        bind("end", End, "TextField"),
+        bind("escape", StopEditing, "TextField"),
+        bind("enter", Submit, "TextField"),
+    ]);
+    cx.bind_keys(platform_text_bindings());
+
+    // Canvas navigation; handlers ignore these while a box is being edited
+    cx.bind_keys([
+        bind("left", PanLeft, "viewport_app"),
+        bind("right", PanRight, "viewport_app"),
+        bind("up", PanUp, "viewport_app"),
+        bind("down", PanDown, "viewport_app"),
+        bind("tab", FocusNextBox, "viewport_app"),
+        bind("shift-tab", FocusPreviousBox, "viewport_app"),
+        bind("alt-left", FocusBoxLeft, "viewport_app"),
+        bind("alt-right", FocusBoxRight, "viewport_app"),
+        bind("alt-up", FocusBoxUp, "viewport_app"),
+        bind("alt-down", FocusBoxDown, "viewport_app"),
+        bind("enter", EditFocusedBox, "viewport_app"),
+        bind("escape", ClearSelection, "viewport_app"),
+        bind("shift-left", NudgeLeftByGrid, "viewport_app"),
+        bind("shift-right", NudgeRightByGrid, "viewport_app"),
+        bind("shift-up", NudgeUpByGrid, "viewport_app"),
+        bind("shift-down", NudgeDownByGrid, "viewport_app"),
+        bind("primary-z", Undo, "viewport_app"),
+        bind("primary-shift-z", Redo, "viewport_app"),
+        bind("primary-c", CopyBoxes, "viewport_app"),
+        bind("primary-x", CutBoxes, "viewport_app"),
+        bind("primary-v", PasteBoxes, "viewport_app"),
+        bind("primary-d", DuplicateBoxes, "viewport_app"),
+        bind("primary-shift-i", InsertImage, "viewport_app"),
+        bind("primary-shift-e", EmbedImages, "viewport_app"),
+        bind("primary-s", SaveBoard, "viewport_app"),
+        bind("primary-o", OpenBoard, "viewport_app"),
+        bind("alt-1", SetShapeRectangle, "viewport_app"),
+        bind("alt-2", SetShapeRounded, "viewport_app"),
+        bind("alt-3", SetShapeEllipse, "viewport_app"),
+        bind("alt-4", SetShapeDiamond, "viewport_app"),
+        bind("alt-5", SetShapeStickyNote, "viewport_app"),
+        bind("primary-alt-c", CopyStyle, "viewport_app"),
+        bind("primary-alt-v", PasteStyle, "viewport_app"),
+        bind("primary-]", BringForward, "viewport_app"),
+        bind("primary-[", SendBackward, "viewport_app"),
+        bind("primary-shift-]", BringToFront, "viewport_app"),
+        bind("primary-shift-[", SendToBack, "viewport_app"),
+        bind("primary-shift-c", ToggleCollisions, "viewport_app"),
+        bind("primary-g", FrameSelection, "viewport_app"),
+        bind("primary-alt-g", ToggleFrameCollapsed, "viewport_app"),
+        bind("primary-l", ToggleLocked, "viewport_app"),
+        bind("primary-shift-h", ToggleHidden, "viewport_app"),
+        bind("primary-alt-h", ToggleShowHidden, "viewport_app"),
+        bind("primary-f", OpenSearch, "viewport_app"),
+        bind("primary-alt-f", ToggleReplace, "viewport_app"),
+        bind("enter", SearchNext, "SearchBar"),
+        bind("shift-enter", SearchPrevious, "SearchBar"),
+        bind("escape", CloseSearch, "SearchBar"),
+        bind("alt-c", ToggleSearchCaseSensitive, "SearchBar"),
+        bind("alt-r", ToggleSearchRegex, "SearchBar"),
+        bind("enter", ReplaceOne, "ReplaceRow"),
+        bind("primary-enter", ReplaceAll, "ReplaceRow"),
+    ]);
+}
+
+/// Text shortcuts only macOS has. The character palette is among them since
+/// no other platform offers one; there `ShowCharacterPalette` stays unbound.
+#[cfg(target_os = "macos")]
+fn platform_text_bindings() -> Vec<KeyBinding> {
+    vec![
+        bind("primary-left", Home, "TextField"),
+        bind("primary-right", End, "TextField"),
+        bind("ctrl-primary-space", ShowCharacterPalette, "TextField"),
+    ]
+}
+
+#[cfg(not(target_os = "macos"))]
+fn platform_text_bindings() -> Vec<KeyBinding> {
+    Vec::new()
+}
+
+fn bind<A: Action>(keys: &str, action: A, context: &str) -> KeyBinding {
+    KeyBinding::new(&keys.replace("primary", PRIMARY), action, Some(context))
+}
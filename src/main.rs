@@ -1,26 +1,16 @@
-use components::text_field::*;
+use common::{settings::Settings, theme::Themes};
+use components::viewport::ViewportApp;
 use gpui::*;
 
 mod common;
 mod components;
+mod keymap;
 
 fn main() {
     Application::new().run(|app: &mut App| {
-        // Add key bindings for text editing functionality
-        app.bind_keys([
-            KeyBinding::new("backspace", Backspace, None),
-            KeyBinding::new("delete", Delete, None),
-            KeyBinding::new("left", Left, None),
-            KeyBinding::new("right", Right, None),
-            KeyBinding::new("shift-left", SelectLeft, None),
-            KeyBinding::new("shift-right", SelectRight, None),
-            KeyBinding::new("cmd-a", SelectAll, None),
-            KeyBinding::new("cmd-v", Paste, None),
-            KeyBinding::new("cmd-c", Copy, None),
-            KeyBinding::new("cmd-x", Cut, None),
-            KeyBinding::new("home", Home, None),
-            KeyBinding::new("end", End, None),
-        ]);
+        keymap::bind_default_keys(app);
+        Settings::init(app);
+        Themes::init(app);
 
         let bounds = Bounds::centered(None, size(px(800.0), px(600.0)), app);
         app.open_window(
@@ -28,7 +18,11 @@ fn main() {
                 window_bounds: Some(WindowBounds::Windowed(bounds)),
                 ..Default::default()
             },
-            |_, cx| cx.new(|cx| components::viewport::ViewportApp::new(cx)),
+            |window, cx| {
+                let view = cx.new(|cx| ViewportApp::new(window, cx));
+                window.focus(&view.focus_handle(cx));
+                view
+            },
         )
         .unwrap();
     });